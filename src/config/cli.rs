@@ -2,6 +2,9 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
+use crate::model::arch::Arch;
+use crate::model::os::OS;
+
 #[derive(Parser, Debug)]
 #[command(author="Dmitrii Kovanikov <kovanikov@gmail.com>", version, about="A CLI tool to manage other CLI tools", long_about = None)]
 pub struct Cli {
@@ -72,7 +75,41 @@ pub enum Command {
     },
 
     /// Sync all tools specified in configuration file or the only one specified in the command line
-    Sync { tool: Option<String> },
+    Sync {
+        tool: Option<String>,
+
+        /// Ignore versions pinned in the lock file and fetch the latest releases
+        #[arg(long)]
+        update: bool,
+
+        /// Fail if resolved releases differ from the ones pinned in the lock file
+        #[arg(long, conflicts_with_all = ["update", "frozen"])]
+        locked: bool,
+
+        /// Install assets pinned in the lock file without fetching release info
+        #[arg(long, conflicts_with = "update")]
+        frozen: bool,
+
+        /// Print what would be installed without downloading anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Fail instead of waiting when another 'tool-sync' process is using the store directory
+        #[arg(long)]
+        no_wait: bool,
+
+        /// Explain why each asset was selected
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Install tools for another OS: linux, macos or windows
+        #[arg(long, value_name = "os", value_parser = parse_os)]
+        target_os: Option<OS>,
+
+        /// Install tools for another CPU architecture: x86_64 or aarch64
+        #[arg(long, value_name = "arch", value_parser = parse_arch)]
+        target_arch: Option<Arch>,
+    },
 
     /// Print a default .tool.toml configuration to std out
     DefaultConfig {
@@ -82,5 +119,40 @@ pub enum Command {
     },
 
     /// Install a tool if it is hardcoded into internal database
-    Install { name: String },
+    Install {
+        name: String,
+
+        /// Install the tool for another OS: linux, macos or windows
+        #[arg(long, value_name = "os", value_parser = parse_os)]
+        target_os: Option<OS>,
+
+        /// Install the tool for another CPU architecture: x86_64 or aarch64
+        #[arg(long, value_name = "arch", value_parser = parse_arch)]
+        target_arch: Option<Arch>,
+    },
+
+    /// List all tools installed by 'tool-sync' in the store directory
+    List,
+
+    /// Compare installed versions of all tools from the configuration file
+    /// with their latest releases without downloading anything
+    Outdated,
+
+    /// Remove a tool installed by 'tool-sync' from the store directory
+    Uninstall { name: String },
+
+    /// Remove all tools installed by 'tool-sync' that are no longer listed in
+    /// the configuration file
+    Prune,
+
+    /// Switch a tool back to its previously installed version
+    Rollback { name: String },
+}
+
+fn parse_os(name: &str) -> Result<OS, String> {
+    OS::from_name(name).ok_or_else(|| String::from("expected one of: linux, macos, windows"))
+}
+
+fn parse_arch(name: &str) -> Result<Arch, String> {
+    Arch::from_name(name).ok_or_else(|| String::from("expected one of: x86_64, aarch64"))
 }
@@ -3,8 +3,10 @@ use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use crate::err;
-use crate::model::asset_name::AssetName;
+use crate::model::asset_name::{AssetName, AssetRules};
+use crate::model::platform::Platform;
 use crate::model::tool::{ToolInfo, ToolInfoTag};
+use crate::model::variables::Variables;
 
 /// Stores global information about the tool installation process and detailed
 /// info about installing each particular tool.
@@ -23,57 +25,139 @@ pub struct Config {
 /// Additional details, telling how to download a tool
 #[derive(Debug, PartialEq, Eq)]
 pub struct ConfigAsset {
+    /// Name of the known tool to configure, e.g. "ripgrep" in the
+    /// '[ripgrep-13]' section
+    /// Defaults to the name of the section
+    pub tool: Option<String>,
+
     /// GitHub repository author
     pub owner: Option<String>,
 
     /// GitHub repository name
     pub repo: Option<String>,
 
-    /// Executable name inside the .tar.gz or .zip archive
+    /// Names of executables inside the .tar.gz or .zip archive
     /// Defaults to `repo` if not specified
-    pub exe_name: Option<String>,
+    pub exe_name: Option<Vec<String>>,
+
+    /// Name of the first executable in the store directory
+    /// Defaults to `exe_name` if not specified
+    pub install_as: Option<String>,
+
+    /// Glob with the path to executables inside the archive, e.g.
+    /// "*/bin/{exe}"
+    /// Defaults to searching in common locations and then everywhere
+    pub exe_path: Option<String>,
+
+    /// Install the only executable in the archive when `exe_name` isn't found
+    /// Defaults to false
+    pub auto_exe: Option<bool>,
 
     /// Release tag to download
     /// Defaults to the latest release
     pub tag: Option<String>,
 
+    /// Which releases to track
+    /// Defaults to stable releases
+    pub channel: Option<Channel>,
+
     /// Name of the specific asset to download
     pub asset_name: AssetName,
 
+    /// Preferred and excluded assets. Global rules from the top of the config
+    /// are already merged into them
+    pub asset_rules: AssetRules,
+
     /// Proxy which will get used for all communication
     pub proxy: Option<ureq::Proxy>,
 }
 
+/// Which releases of a tool to install
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Only stable releases
+    Stable,
+
+    /// The newest release including pre-releases
+    Prerelease,
+
+    /// A moving tag (e.g. "nightly"), reinstalled whenever its assets change
+    Nightly,
+}
+
+/// Tag of the nightly channel when the config doesn't specify one
+pub const NIGHTLY_TAG: &str = "nightly";
+
+impl Channel {
+    pub const NAMES: [&'static str; 3] = ["stable", "prerelease", "nightly"];
+
+    pub fn from_name(name: &str) -> Option<Channel> {
+        match name {
+            "stable" => Some(Channel::Stable),
+            "prerelease" => Some(Channel::Prerelease),
+            "nightly" => Some(Channel::Nightly),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigAsset {
+    /// Placeholders of the tool in the given section. `{tool}` is the known
+    /// tool from the 'tool' field and defaults to the name of the section.
+    pub fn variables(&self, section_name: &str, tag: &str, platform: &Platform) -> Variables {
+        let tool_name = self.tool.as_deref().unwrap_or(section_name);
+
+        Variables::new(tool_name, tag, platform)
+    }
+}
+
 impl From<ToolInfo> for ConfigAsset {
     fn from(tool_info: ToolInfo) -> Self {
-        let tag = match tool_info.tag {
-            ToolInfoTag::Specific(version) => Some(version),
-            ToolInfoTag::Latest => None,
+        let (tag, channel) = match tool_info.tag {
+            ToolInfoTag::Specific(version) | ToolInfoTag::Range(version) => (Some(version), None),
+            ToolInfoTag::Prerelease => (None, Some(Channel::Prerelease)),
+            ToolInfoTag::Latest => (None, None),
         };
 
         Self {
+            tool: None,
             owner: Some(tool_info.owner),
             repo: Some(tool_info.repo),
-            exe_name: Some(tool_info.exe_name),
+            exe_name: Some(tool_info.exe_names),
+            install_as: tool_info.install_as,
+            exe_path: tool_info.exe_path,
+            auto_exe: Some(tool_info.auto_exe),
             tag,
+            channel,
             asset_name: tool_info.asset_name,
+            asset_rules: tool_info.asset_rules,
 
-            /// Hardcoded tools don't supply their own proxy automatically
+            // Hardcoded tools don't supply their own proxy automatically
             proxy: None,
         }
     }
 }
 
 impl Config {
+    /// Shellexpands store directory without checking whether it exists
+    pub fn store_directory(&self) -> PathBuf {
+        match shellexpand::full(&self.store_directory) {
+            Err(e) => err::abort_with(e),
+            Ok(cow_path) => PathBuf::from(cow_path.into_owned()),
+        }
+    }
+
+    /// Shellexpands store directory and returns it only if it exists. Commands
+    /// that only read the store directory treat a missing one as having no
+    /// tools instead of failing like `ensure_store_directory`.
+    pub fn existing_store_directory(&self) -> Option<PathBuf> {
+        Some(self.store_directory()).filter(|store_directory| store_directory.is_dir())
+    }
+
     /// Shellexpands store directory, check whether it exists and exits with
     /// error if 'store_directory' doesn't exist
     pub fn ensure_store_directory(&self) -> PathBuf {
-        let expanded_store_directory = shellexpand::full(&self.store_directory);
-
-        let store_directory = match expanded_store_directory {
-            Err(e) => err::abort_with(e),
-            Ok(cow_path) => PathBuf::from(cow_path.into_owned()),
-        };
+        let store_directory = self.store_directory();
 
         let has_store_directory = store_directory.as_path().is_dir();
 
@@ -29,18 +29,50 @@ fn config_template() -> String {
 #     repo      = "ripgrep"     # GitHub repository name
 #     exe_name  = "rg"          # Executable name inside the asset
 
+#     Uncomment to install the executable under a different name
+#     install_as = "rg13"
+
+#     Uncomment if the executable is nested deeply inside the archive.
+#     {{exe}} expands to the executable name
+#     exe_path = "*/bin/{{exe}}"
+
+#     Uncomment to install the only executable in the asset when 'exe_name'
+#     isn't found there
+#     auto_exe = true
+
+#     Uncomment to configure a known tool in a section with another name,
+#     e.g. [ripgrep-13], to install several versions of it side by side
+#     tool = "ripgrep"
+
 #     Uncomment to download a specific version or tag.
+#     Version ranges like "^13" or ">=0.22, <0.24" pick the highest matching release.
 #     Without this tag latest will be used
 #     tag       = "13.0.0"
 
+#     Uncomment to track pre-releases ("prerelease") or a moving tag like
+#     "nightly" ("nightly", the tag above names the moving tag)
+#     channel   = "prerelease"
+
 #     Asset name to download on linux OSes
+#     Use {{ glob = "..." }} or {{ regex = "..." }} when a substring is not enough
+#     Placeholders {{version}}, {{version_no_v}}, {{os}}, {{arch}} and {{tool}}
+#     expand after the release is resolved. {{libc}} expands to "gnu" or "musl"
 #     asset_name.linux = "x86_64-unknown-linux-musl"
 
 #     Uncomment if you want to install on macOS as well
 #     asset_name.macos = "apple-darwin"
 
+#     Uncomment to install a different asset on Apple Silicon
+#     asset_name.macos-aarch64 = "aarch64-apple-darwin"
+
 #     Uncomment if you want to install on Windows as well
-#     asset_name.windows = "x86_64-pc-windows-msvc""###,
+#     asset_name.windows = "x86_64-pc-windows-msvc"
+
+#     Uncomment to resolve several matching assets. Preferences go in order
+#     and excluded assets are never installed. Put these lists before all
+#     tools to apply them to every tool
+#     prefer  = ["musl", ".tar.gz"]
+#     exclude = [".deb", ".sha256"]"###,
         version = env!("CARGO_PKG_VERSION"),
     )
 }
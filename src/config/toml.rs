@@ -4,9 +4,10 @@ use std::fs;
 use std::path::PathBuf;
 use toml::{map::Map, Value};
 
-use crate::config::schema::{Config, ConfigAsset};
+use crate::config::schema::{Channel, Config, ConfigAsset};
 use crate::infra::err;
-use crate::model::asset_name::AssetName;
+use crate::model::arch::Arch;
+use crate::model::asset_name::{AssetName, AssetPattern, AssetRules};
 use crate::model::os::OS;
 
 #[derive(Debug, PartialEq)]
@@ -26,6 +27,15 @@ pub enum DecodeError {
         expected: Value,
         found: Value,
     },
+    InvalidValue {
+        key: String,
+        expected: Vec<String>,
+        found: String,
+    },
+    UnknownKey {
+        key: String,
+        expected: String,
+    },
 }
 
 impl Display for TomlError {
@@ -56,6 +66,20 @@ impl Display for DecodeError {
                 expected.type_str(),
                 found.type_str()
             ),
+            DecodeError::InvalidValue {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "unexpected value '{}={}': expected one of: {}",
+                key,
+                found,
+                expected.join(", ")
+            ),
+            DecodeError::UnknownKey { key, expected } => {
+                write!(f, "unknown key '{}': expected {}", key, expected)
+            }
         }
     }
 }
@@ -118,9 +142,14 @@ fn decode_config(toml: Value, proxy: Option<String>) -> Result<Config, DecodeErr
         .as_table()
         .expect("unable to parse config file to a table");
 
+    let global_rules = decode_asset_rules("", table)?;
+
     for (key, val) in table.iter() {
         if let Value::Table(table) = val {
-            tools.insert(key.clone(), decode_config_asset(table, &proxy));
+            tools.insert(
+                key.clone(),
+                decode_config_asset(key, table, &proxy, &global_rules)?,
+            );
         }
     }
 
@@ -131,19 +160,36 @@ fn decode_config(toml: Value, proxy: Option<String>) -> Result<Config, DecodeErr
     })
 }
 
-fn decode_config_asset(table: &Map<String, Value>, proxy: &Option<String>) -> ConfigAsset {
+fn decode_config_asset(
+    tool_name: &str,
+    table: &Map<String, Value>,
+    proxy: &Option<String>,
+    global_rules: &AssetRules,
+) -> Result<ConfigAsset, DecodeError> {
+    let tool = str_by_key(table, "tool");
     let owner = str_by_key(table, "owner");
     let repo = str_by_key(table, "repo");
-    let exe_name = str_by_key(table, "exe_name");
-    let asset_name = decode_asset_name(table);
+    let exe_name = decode_exe_name(tool_name, table)?;
+    let install_as = decode_install_as(tool_name, table)?;
+    let exe_path = decode_exe_path(tool_name, table)?;
+    let auto_exe = decode_auto_exe(tool_name, table)?;
+    let asset_name = decode_asset_name(tool_name, table)?;
     let tag = str_by_key(table, "tag");
+    let channel = decode_channel(tool_name, table)?;
+    let asset_rules = decode_asset_rules(&format!("{}.", tool_name), table)?;
 
     let mut config_asset = ConfigAsset {
+        tool,
         owner,
         repo,
         exe_name,
+        install_as,
+        exe_path,
+        auto_exe,
         asset_name,
+        asset_rules: merge_asset_rules(asset_rules, global_rules),
         tag,
+        channel,
         proxy: None,
     };
     if let Some(p) = proxy {
@@ -154,31 +200,230 @@ fn decode_config_asset(table: &Map<String, Value>, proxy: &Option<String>) -> Co
             )
         }));
     };
-    config_asset
+    Ok(config_asset)
 }
 
-fn decode_asset_name(table: &Map<String, Value>) -> AssetName {
-    match table.get("asset_name").and_then(|t| t.as_table()) {
-        None => AssetName {
-            linux: None,
-            macos: None,
-            windows: None,
+/// Executable name is either a single string or a list of strings for assets
+/// with several executables
+fn decode_exe_name(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Option<Vec<String>>, DecodeError> {
+    match table.get("exe_name") {
+        None => Ok(None),
+        Some(Value::String(exe_name)) => Ok(Some(vec![exe_name.clone()])),
+        Some(_) => {
+            let exe_names = str_list_by_key(table, &format!("{}.", tool_name), "exe_name")?;
+
+            if exe_names.is_empty() {
+                Err(DecodeError::InvalidValue {
+                    key: format!("{}.exe_name", tool_name),
+                    expected: vec![String::from("a non-empty list of names")],
+                    found: String::from("[]"),
+                })
+            } else {
+                Ok(Some(exe_names))
+            }
+        }
+    }
+}
+
+/// The installed executable name must be a file name and not a path
+fn decode_install_as(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Option<String>, DecodeError> {
+    match str_by_key(table, "install_as") {
+        Some(install_as) if install_as.is_empty() || install_as.contains(['/', '\\']) => {
+            Err(DecodeError::InvalidValue {
+                key: format!("{}.install_as", tool_name),
+                expected: vec![String::from("a file name without directories")],
+                found: install_as,
+            })
+        }
+        install_as => Ok(install_as),
+    }
+}
+
+fn decode_exe_path(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Option<String>, DecodeError> {
+    match str_by_key(table, "exe_path") {
+        None => Ok(None),
+        Some(exe_path) => match glob::Pattern::new(&exe_path) {
+            Ok(_) => Ok(Some(exe_path)),
+            Err(e) => Err(DecodeError::InvalidValue {
+                key: format!("{}.exe_path", tool_name),
+                expected: vec![format!("a valid glob ({})", e.msg)],
+                found: exe_path,
+            }),
         },
+    }
+}
 
-        Some(table) => {
-            let linux = str_by_key(table, &OS::Linux.to_string());
-            let macos = str_by_key(table, &OS::MacOS.to_string());
-            let windows = str_by_key(table, &OS::Windows.to_string());
+fn decode_auto_exe(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Option<bool>, DecodeError> {
+    match table.get("auto_exe") {
+        None => Ok(None),
+        Some(Value::Boolean(auto_exe)) => Ok(Some(*auto_exe)),
+        Some(other) => Err(DecodeError::InvalidType {
+            key: format!("{}.auto_exe", tool_name),
+            expected: Value::Boolean(true),
+            found: other.clone(),
+        }),
+    }
+}
 
-            AssetName {
-                linux,
-                macos,
-                windows,
+fn decode_channel(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Option<Channel>, DecodeError> {
+    match str_by_key(table, "channel") {
+        None => Ok(None),
+        Some(name) => match Channel::from_name(&name) {
+            Some(channel) => Ok(Some(channel)),
+            None => Err(DecodeError::InvalidValue {
+                key: format!("{}.channel", tool_name),
+                expected: Channel::NAMES.iter().map(|name| name.to_string()).collect(),
+                found: name,
+            }),
+        },
+    }
+}
+
+fn decode_asset_name(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<AssetName, DecodeError> {
+    let mut asset_name = AssetName {
+        linux: None,
+        macos: None,
+        windows: None,
+        arch_specific: BTreeMap::new(),
+    };
+
+    let table = match table.get("asset_name").and_then(|t| t.as_table()) {
+        None => return Ok(asset_name),
+        Some(table) => table,
+    };
+
+    // Keys are either an OS (e.g. 'linux') or an OS with an architecture
+    // (e.g. 'linux-aarch64')
+    for (key, value) in table.iter() {
+        let full_key = format!("{}.asset_name.{}", tool_name, key);
+        let pattern = decode_asset_pattern(&full_key, value)?;
+
+        let (os_name, arch_name) = match key.split_once('-') {
+            None => (key.as_str(), None),
+            Some((os_name, arch_name)) => (os_name, Some(arch_name)),
+        };
+
+        match (OS::from_name(os_name), arch_name.map(Arch::from_name)) {
+            (Some(OS::Linux), None) => asset_name.linux = Some(pattern),
+            (Some(OS::MacOS), None) => asset_name.macos = Some(pattern),
+            (Some(OS::Windows), None) => asset_name.windows = Some(pattern),
+            (Some(os), Some(Some(arch))) => {
+                asset_name.arch_specific.insert((os, arch), pattern);
+            }
+            _ => {
+                return Err(DecodeError::UnknownKey {
+                    key: full_key,
+                    expected: String::from(
+                        "an OS (linux, macos, windows) optionally followed by an architecture (e.g. linux-aarch64)",
+                    ),
+                })
+            }
+        }
+    }
+
+    Ok(asset_name)
+}
+
+/// Asset name is either a plain string for the substring search or a table
+/// with a single 'glob' or 'regex' key
+fn decode_asset_pattern(key: &str, value: &Value) -> Result<AssetPattern, DecodeError> {
+    match value {
+        Value::String(substring) => Ok(AssetPattern::Substring(substring.clone())),
+        value => {
+            let glob = value.get("glob").and_then(|v| v.as_str());
+            let regex = value.get("regex").and_then(|v| v.as_str());
+
+            match (glob, regex) {
+                (Some(glob), None) => Ok(AssetPattern::Glob(glob.to_owned())),
+                (None, Some(regex)) => Ok(AssetPattern::Regex(regex.to_owned())),
+                _ => Err(DecodeError::InvalidValue {
+                    key: key.to_owned(),
+                    expected: vec![
+                        String::from("\"substring\""),
+                        String::from("{ glob = \"...\" }"),
+                        String::from("{ regex = \"...\" }"),
+                    ],
+                    found: value.to_string(),
+                }),
             }
         }
     }
 }
 
+/// Decode the 'prefer' and 'exclude' lists of a tool or of the whole config.
+/// The key prefix is either empty or the tool name with a dot.
+fn decode_asset_rules(
+    key_prefix: &str,
+    table: &Map<String, Value>,
+) -> Result<AssetRules, DecodeError> {
+    Ok(AssetRules {
+        prefer: str_list_by_key(table, key_prefix, "prefer")?,
+        exclude: str_list_by_key(table, key_prefix, "exclude")?,
+    })
+}
+
+/// Preferences of the tool go before the global ones. Assets excluded either
+/// globally or for the tool are never selected.
+fn merge_asset_rules(tool_rules: AssetRules, global_rules: &AssetRules) -> AssetRules {
+    let mut prefer = tool_rules.prefer;
+    for preferred in &global_rules.prefer {
+        if !prefer.contains(preferred) {
+            prefer.push(preferred.clone());
+        }
+    }
+
+    let mut exclude = global_rules.exclude.clone();
+    for excluded in tool_rules.exclude {
+        if !exclude.contains(&excluded) {
+            exclude.push(excluded);
+        }
+    }
+
+    AssetRules { prefer, exclude }
+}
+
+fn str_list_by_key(
+    table: &Map<String, Value>,
+    key_prefix: &str,
+    key: &str,
+) -> Result<Vec<String>, DecodeError> {
+    let invalid_type = |found: &Value| DecodeError::InvalidType {
+        key: format!("{}{}", key_prefix, key),
+        expected: Value::Array(vec![Value::String("some_value".into())]),
+        found: found.clone(),
+    };
+
+    match table.get(key) {
+        None => Ok(vec![]),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => Ok(s.clone()),
+                other => Err(invalid_type(other)),
+            })
+            .collect(),
+        Some(other) => Err(invalid_type(other)),
+    }
+}
+
 fn str_by_key(table: &Map<String, Value>, key: &str) -> Option<String> {
     table.get(key).and_then(|v| v.as_str()).map(String::from)
 }
@@ -187,6 +432,8 @@ fn str_by_key(table: &Map<String, Value>, key: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    use crate::model::platform::Platform;
+
     #[test]
     fn test_toml_error_display_io() {
         let toml_error = TomlError::IO(String::from("some file error!"));
@@ -327,15 +574,22 @@ mod tests {
             tools: BTreeMap::from([(
                 "ripgrep".to_owned(),
                 ConfigAsset {
+                    tool: None,
                     owner: None,
                     repo: None,
                     exe_name: None,
+                    install_as: None,
+                    exe_path: None,
+                    auto_exe: None,
                     asset_name: AssetName {
                         linux: None,
                         macos: None,
                         windows: None,
+                        arch_specific: BTreeMap::new(),
                     },
                     tag: None,
+                    channel: None,
+                    asset_rules: AssetRules::default(),
                     proxy: None,
                 },
             )]),
@@ -362,30 +616,44 @@ mod tests {
                 (
                     "ripgrep".to_owned(),
                     ConfigAsset {
+                        tool: None,
                         owner: None,
                         repo: None,
                         exe_name: None,
+                        install_as: None,
+                        exe_path: None,
+                        auto_exe: None,
                         asset_name: AssetName {
                             linux: None,
                             macos: None,
                             windows: None,
+                            arch_specific: BTreeMap::new(),
                         },
                         tag: None,
+                        channel: None,
+                        asset_rules: AssetRules::default(),
                         proxy: None,
                     },
                 ),
                 (
                     "bat".to_owned(),
                     ConfigAsset {
+                        tool: None,
                         owner: None,
                         repo: None,
                         exe_name: None,
+                        install_as: None,
+                        exe_path: None,
+                        auto_exe: None,
                         asset_name: AssetName {
                             linux: None,
                             macos: None,
                             windows: None,
+                            arch_specific: BTreeMap::new(),
                         },
                         tag: None,
+                        channel: None,
+                        asset_rules: AssetRules::default(),
                         proxy: None,
                     },
                 ),
@@ -413,15 +681,22 @@ mod tests {
             tools: BTreeMap::from([(
                 "ripgrep".to_owned(),
                 ConfigAsset {
+                    tool: None,
                     owner: Some("me".to_owned()),
                     repo: None,
                     exe_name: None,
+                    install_as: None,
+                    exe_path: None,
+                    auto_exe: None,
                     asset_name: AssetName {
-                        linux: Some("R2D2".to_owned()),
+                        linux: Some(AssetPattern::Substring("R2D2".to_owned())),
                         macos: None,
                         windows: None,
+                        arch_specific: BTreeMap::new(),
                     },
                     tag: None,
+                    channel: None,
+                    asset_rules: AssetRules::default(),
                     proxy: None,
                 },
             )]),
@@ -453,15 +728,22 @@ mod tests {
             tools: BTreeMap::from([(
                 "ripgrep".to_owned(),
                 ConfigAsset {
+                    tool: None,
                     owner: Some("me".to_owned()),
                     repo: Some("some_repo".to_owned()),
-                    exe_name: Some("rg".to_owned()),
+                    exe_name: Some(vec!["rg".to_owned()]),
+                    install_as: None,
+                    exe_path: None,
+                    auto_exe: None,
                     asset_name: AssetName {
-                        linux: Some("R2D2".to_owned()),
-                        macos: Some("C3-PO".to_owned()),
-                        windows: Some("IG-88".to_owned()),
+                        linux: Some(AssetPattern::Substring("R2D2".to_owned())),
+                        macos: Some(AssetPattern::Substring("C3-PO".to_owned())),
+                        windows: Some(AssetPattern::Substring("IG-88".to_owned())),
+                        arch_specific: BTreeMap::new(),
                     },
                     tag: Some("4.2.0".to_owned()),
+                    channel: None,
+                    asset_rules: AssetRules::default(),
                     proxy: None,
                 },
             )]),
@@ -470,4 +752,321 @@ mod tests {
 
         assert_eq!(res, Ok(cfg));
     }
+
+    #[test]
+    fn tool_with_channel() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [neovim]
+            channel = "nightly"
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+
+        assert_eq!(res.tools["neovim"].channel, Some(Channel::Nightly));
+    }
+
+    #[test]
+    fn tool_with_unknown_channel() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [neovim]
+            channel = "beta"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert_eq!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidValue {
+                key: String::from("neovim.channel"),
+                expected: vec![
+                    String::from("stable"),
+                    String::from("prerelease"),
+                    String::from("nightly")
+                ],
+                found: String::from("beta"),
+            }))
+        );
+    }
+
+    #[test]
+    fn asset_name_patterns() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            asset_name.linux = { glob = "*x86_64*linux-musl.tar.gz" }
+            asset_name.macos = { regex = "apple-darwin\\.tar\\.gz$" }
+            asset_name.windows = "x86_64-pc-windows-msvc"
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+
+        assert_eq!(
+            res.tools["ripgrep"].asset_name,
+            AssetName {
+                linux: Some(AssetPattern::Glob(String::from(
+                    "*x86_64*linux-musl.tar.gz"
+                ))),
+                macos: Some(AssetPattern::Regex(String::from(r"apple-darwin\.tar\.gz$"))),
+                windows: Some(AssetPattern::Substring(String::from(
+                    "x86_64-pc-windows-msvc"
+                ))),
+                arch_specific: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn asset_name_unknown_pattern() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            asset_name.linux = { wildcard = "*musl*" }
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidValue { key, .. })) if key == "ripgrep.asset_name.linux"
+        ));
+    }
+
+    #[test]
+    fn asset_name_with_arch() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            asset_name.linux = "x86_64-unknown-linux-musl"
+            asset_name.linux-aarch64 = "aarch64-unknown-linux-gnu"
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+
+        assert_eq!(
+            res.tools["ripgrep"].asset_name,
+            AssetName {
+                linux: Some(AssetPattern::Substring(String::from(
+                    "x86_64-unknown-linux-musl"
+                ))),
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::from([(
+                    (OS::Linux, Arch::Aarch64),
+                    AssetPattern::Substring(String::from("aarch64-unknown-linux-gnu"))
+                )]),
+            }
+        );
+    }
+
+    #[test]
+    fn asset_name_unknown_arch() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            asset_name.linux-riscv64 = "riscv64"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::UnknownKey { key, .. })) if key == "ripgrep.asset_name.linux-riscv64"
+        ));
+    }
+
+    #[test]
+    fn asset_rules_merged_with_global() {
+        let toml = r#"
+            store_directory = "pancake"
+            prefer = ["musl", ".tar.gz"]
+            exclude = [".sha256"]
+
+            [ripgrep]
+            prefer = ["gnu", "musl"]
+            exclude = [".deb"]
+
+            [bat]
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+
+        assert_eq!(
+            res.tools["ripgrep"].asset_rules,
+            AssetRules {
+                prefer: vec![
+                    String::from("gnu"),
+                    String::from("musl"),
+                    String::from(".tar.gz")
+                ],
+                exclude: vec![String::from(".sha256"), String::from(".deb")],
+            }
+        );
+        assert_eq!(
+            res.tools["bat"].asset_rules,
+            AssetRules {
+                prefer: vec![String::from("musl"), String::from(".tar.gz")],
+                exclude: vec![String::from(".sha256")],
+            }
+        );
+    }
+
+    #[test]
+    fn asset_rules_invalid_type() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            prefer = "musl"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidType { key, .. })) if key == "ripgrep.prefer"
+        ));
+    }
+
+    #[test]
+    fn exe_name_list() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [fzf]
+            exe_name = ["fzf", "fzf-tmux"]
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+
+        assert_eq!(
+            res.tools["fzf"].exe_name,
+            Some(vec![String::from("fzf"), String::from("fzf-tmux")])
+        );
+    }
+
+    #[test]
+    fn exe_name_empty_list() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [fzf]
+            exe_name = []
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidValue { key, .. })) if key == "fzf.exe_name"
+        ));
+    }
+
+    #[test]
+    fn tool_with_install_as() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [difftastic]
+            install_as = "difftastic"
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+
+        assert_eq!(
+            res.tools["difftastic"].install_as,
+            Some(String::from("difftastic"))
+        );
+    }
+
+    #[test]
+    fn install_as_with_directory() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            install_as = "bin/rg"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidValue { key, .. })) if key == "ripgrep.install_as"
+        ));
+    }
+
+    #[test]
+    fn exe_path_invalid_glob() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            exe_path = "[bin/{exe}"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidValue { key, .. })) if key == "ripgrep.exe_path"
+        ));
+    }
+
+    #[test]
+    fn auto_exe_invalid_type() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [mytool]
+            auto_exe = "yes"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert!(matches!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidType { key, .. })) if key == "mytool.auto_exe"
+        ));
+    }
+
+    #[test]
+    fn tool_placeholder_is_tool_field() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep-13]
+            tool = "ripgrep"
+            tag = "13.0.0"
+
+            [fd]
+        "#;
+
+        let res = parse_string(toml, None).expect("This should not fail");
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::X86_64,
+            libc: None,
+        };
+
+        assert_eq!(
+            res.tools["ripgrep-13"]
+                .variables("ripgrep-13", "13.0.0", &platform)
+                .expand("{tool}-{version}"),
+            "ripgrep-13.0.0"
+        );
+        assert_eq!(
+            res.tools["fd"]
+                .variables("fd", "v8.5.3", &platform)
+                .expand("{tool}-{version}"),
+            "fd-v8.5.3"
+        );
+    }
 }
@@ -2,7 +2,18 @@ use std::env;
 use std::error::Error;
 use std::io::Read;
 
-use crate::model::release::{Asset, Release};
+use semver::VersionReq;
+
+use crate::model::release::{select_newest_release, select_release, Asset, Release};
+use crate::model::repo::RepoError;
+use crate::model::tool::ToolInfoTag;
+
+/// Maximum number of releases GitHub returns on a single page
+const RELEASES_PER_PAGE: u32 = 100;
+
+/// Only this many pages of releases are fetched to keep the number of API
+/// requests low
+const MAX_RELEASES_PAGES: u32 = 5;
 
 /// GitHub API client to handle all API requests
 #[derive(Debug)]
@@ -24,6 +35,16 @@ impl Client {
         )
     }
 
+    fn releases_url(&self, page: u32) -> String {
+        format!(
+            "https://api.github.com/repos/{owner}/{repo}/releases?per_page={per_page}&page={page}",
+            owner = self.owner,
+            repo = self.repo,
+            per_page = RELEASES_PER_PAGE,
+            page = page,
+        )
+    }
+
     fn asset_url(&self, asset_id: u32) -> String {
         format!(
             "https://api.github.com/repos/{owner}/{repo}/releases/assets/{asset_id}",
@@ -59,10 +80,107 @@ impl Client {
         Ok(release)
     }
 
+    /// Fetch the most recent releases of the repository, newest first
+    pub fn fetch_releases(&self) -> Result<Vec<Release>, Box<dyn Error>> {
+        let mut releases = Vec::new();
+
+        for page in 1..=MAX_RELEASES_PAGES {
+            let page_releases = self.fetch_releases_page(page)?;
+            let is_last_page = page_releases.len() < RELEASES_PER_PAGE as usize;
+
+            releases.extend(page_releases);
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(releases)
+    }
+
+    /// Fetch the most recent release including pre-releases. Pages are fetched
+    /// one by one up to the same limit as in `fetch_releases`: the newest
+    /// release is on the first page unless it's full of drafts.
+    fn fetch_newest_release(&self) -> Result<Option<Release>, Box<dyn Error>> {
+        for page in 1..=MAX_RELEASES_PAGES {
+            let page_releases = self.fetch_releases_page(page)?;
+            let is_last_page = page_releases.len() < RELEASES_PER_PAGE as usize;
+
+            if let Some(release) = select_newest_release(page_releases) {
+                return Ok(Some(release));
+            }
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Fetch a single page of releases, newest first
+    pub fn fetch_releases_page(&self, page: u32) -> Result<Vec<Release>, Box<dyn Error>> {
+        let releases_url = self.releases_url(page);
+
+        let req = match &self.proxy {
+            Some(proxy) => {
+                let agent = ureq::AgentBuilder::new().proxy(proxy.clone()).build();
+
+                add_auth_header(
+                    agent
+                        .get(&releases_url)
+                        .set("Accept", "application/vnd.github+json")
+                        .set("User-Agent", "chshersh/tool-sync-0.2.0"),
+                )
+            }
+            None => add_auth_header(
+                ureq::get(&releases_url)
+                    .set("Accept", "application/vnd.github+json")
+                    .set("User-Agent", "chshersh/tool-sync-0.2.0"),
+            ),
+        };
+
+        let releases: Vec<Release> = req.call()?.into_json()?;
+
+        Ok(releases)
+    }
+
+    /// Fetch the release the tag points to. Tags that don't name a single
+    /// release are resolved by listing releases and the client is updated to
+    /// use the tag of the resolved release.
+    pub fn resolve_release(&mut self, tag: &ToolInfoTag) -> Result<Release, Box<dyn Error>> {
+        let release = match tag {
+            ToolInfoTag::Latest | ToolInfoTag::Specific(_) => return self.fetch_release_info(),
+            ToolInfoTag::Prerelease => self.fetch_newest_release()?,
+            ToolInfoTag::Range(range) => {
+                let version_req = VersionReq::parse(range).map_err(|e| {
+                    format!(
+                        "Invalid version range '{}' in the 'tag' field: {}",
+                        range, e
+                    )
+                })?;
+
+                select_release(self.fetch_releases()?, &version_req)
+            }
+        };
+
+        match release {
+            Some(release) => {
+                self.version = ToolInfoTag::Specific(release.tag_name.clone()).to_str_version();
+                Ok(release)
+            }
+            None => Err(Box::new(RepoError::NotFound {
+                owner: self.owner.clone(),
+                repo: self.repo.clone(),
+                tag: tag.clone(),
+            })),
+        }
+    }
+
     pub fn get_asset_stream(
         &self,
         asset: &Asset,
-    ) -> Result<Box<dyn Read + Send + Sync>, ureq::Error> {
+    ) -> Result<Box<dyn Read + Send + Sync>, Box<dyn Error>> {
         let asset_url = self.asset_url(asset.id);
         let req = match &self.proxy {
             Some(proxy) => {
@@ -97,8 +215,6 @@ fn add_auth_header(req: ureq::Request) -> ureq::Request {
 mod tests {
     use super::*;
 
-    use crate::model::tool::ToolInfoTag;
-
     #[test]
     fn release_url_with_latest_tag_is_correct() {
         let client = Client {
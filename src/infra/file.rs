@@ -0,0 +1,128 @@
+//! Helpers for reading and writing files that 'tool-sync' manages itself
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::infra::err;
+
+/// Read a TOML file with the given description (used in error messages). A
+/// missing file is the same as an empty one.
+pub fn read_toml<T: DeserializeOwned + Default>(path: &Path, description: &str) -> T {
+    if !path.is_file() {
+        return T::default();
+    }
+
+    let contents = fs::read_to_string(path).unwrap_or_else(|e| {
+        err::abort_with(format!(
+            "Error reading {} at path {}: {}",
+            description,
+            path.display(),
+            e
+        ))
+    });
+
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        err::abort_with(format!(
+            "Error parsing {} at path {}: {}",
+            description,
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Write a value to a TOML file prepending the given header, creating parent
+/// directories if needed
+pub fn write_toml<T: Serialize>(path: &Path, description: &str, header: &str, value: &T) {
+    let contents = match toml::to_string(value) {
+        Ok(contents) => contents,
+        Err(e) => err::abort_suggest_issue(e),
+    };
+
+    let result = match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+    .and_then(|_| fs::write(path, format!("{}{}", header, contents)));
+
+    if let Err(e) = result {
+        err::abort_with(format!(
+            "Error writing {} at path {}: {}",
+            description,
+            path.display(),
+            e
+        ));
+    }
+}
+
+/// Hex-encoded SHA-256 of the file contents
+pub fn sha256_digest(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// First bytes of ELF, PE and Mach-O (32-bit, 64-bit and universal)
+/// executables
+const EXECUTABLE_HEADERS: [&[u8]; 7] = [
+    b"\x7fELF",
+    b"MZ",
+    &[0xfe, 0xed, 0xfa, 0xce],
+    &[0xce, 0xfa, 0xed, 0xfe],
+    &[0xfe, 0xed, 0xfa, 0xcf],
+    &[0xcf, 0xfa, 0xed, 0xfe],
+    &[0xca, 0xfe, 0xba, 0xbe],
+];
+
+/// Whether the file has the executable bit or a header of an executable
+pub fn is_executable(path: &Path) -> bool {
+    has_executable_bit(path) || has_executable_header(path)
+}
+
+#[cfg(target_family = "unix")]
+fn has_executable_bit(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn has_executable_bit(_path: &Path) -> bool {
+    false
+}
+
+fn has_executable_header(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(4);
+
+    match File::open(path).and_then(|file| file.take(4).read_to_end(&mut header)) {
+        Ok(_) => EXECUTABLE_HEADERS
+            .iter()
+            .any(|executable_header| header.starts_with(executable_header)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempdir::TempDir;
+
+    #[test]
+    fn executable_by_header() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        let elf = tmp_dir.path().join("tool");
+        let readme = tmp_dir.path().join("README.md");
+        fs::write(&elf, b"\x7fELF\x02\x01\x01").expect("This should not fail");
+        fs::write(&readme, b"# tool").expect("This should not fail");
+
+        assert!(is_executable(&elf));
+        assert!(!is_executable(&readme));
+    }
+}
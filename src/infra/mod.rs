@@ -1,2 +1,4 @@
 pub mod client;
 pub mod err;
+pub mod file;
+pub mod table;
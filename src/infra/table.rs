@@ -0,0 +1,54 @@
+//! Formatting of simple plain-text tables for command outputs
+
+/// Format rows into a table with aligned columns and a header on top. All rows
+/// must have the same number of columns as the header.
+pub fn fmt_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|column| column.len()).collect();
+
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = std::cmp::max(*width, cell.chars().count());
+        }
+    }
+
+    let header_row: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+
+    std::iter::once(&header_row)
+        .chain(rows)
+        .map(|row| fmt_row(row, &widths))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn fmt_row(row: &[String], widths: &[usize]) -> String {
+    row.iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:width$}", cell, width = width))
+        .collect::<Vec<String>>()
+        .join("  ")
+        .trim_end()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned_columns() {
+        let rows = vec![
+            vec!["ripgrep".to_owned(), "13.0.0".to_owned()],
+            vec!["bat".to_owned(), "v0.22.1".to_owned()],
+        ];
+
+        assert_eq!(
+            fmt_table(&["tool", "tag"], &rows),
+            "tool     tag\nripgrep  13.0.0\nbat      v0.22.1"
+        );
+    }
+
+    #[test]
+    fn only_header() {
+        assert_eq!(fmt_table(&["tool", "tag"], &[]), "tool  tag");
+    }
+}
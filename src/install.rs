@@ -3,18 +3,33 @@ use std::path::PathBuf;
 use crate::config::schema::Config;
 use crate::config::toml;
 use crate::infra::err;
+use crate::model::platform::Platform;
 use crate::sync;
 use crate::sync::db::{fmt_tool_names_info, lookup_tool};
+use crate::sync::lock::LockMode;
+use crate::sync::store_lock::LockWait;
+use crate::sync::SyncOptions;
 
 /// Install a single tool
-pub fn install(config_path: PathBuf, name: String, proxy: Option<String>) {
-    toml::with_parsed_file(config_path, proxy, |config| install_tool(config, name))
+pub fn install(config_path: PathBuf, name: String, proxy: Option<String>, platform: Platform) {
+    // Tools installed outside of the config aren't pinned in the lock file
+    let options = SyncOptions {
+        lock_path: None,
+        lock_mode: LockMode::Pinned,
+        dry_run: false,
+        lock_wait: LockWait::Wait,
+        verbose: false,
+        platform,
+    };
+    toml::with_parsed_file(config_path, proxy, |config| {
+        install_tool(config, name, options)
+    })
 }
 
 /// Find if the tool is already mentioned in the config
-fn install_tool(config: Config, name: String) {
+fn install_tool(config: Config, name: String, options: SyncOptions) {
     if let Some(tool_info) = lookup_tool(&name) {
-        sync::sync_single_tool(config, name, tool_info.into());
+        sync::sync_single_tool(config, name, tool_info.into(), options);
     } else {
         let tools = fmt_tool_names_info(|name| format!("    * {name}"));
 
@@ -2,8 +2,12 @@ mod completion;
 mod config;
 mod infra;
 mod install;
+mod list;
 mod model;
+mod outdated;
+mod rollback;
 mod sync;
+mod uninstall;
 
 use clap::{CommandFactory, Parser};
 use clap_complete::generate;
@@ -13,6 +17,10 @@ use std::path::PathBuf;
 use crate::completion::rename_completion_suggestion;
 use crate::config::cli::{Cli, Command};
 use crate::infra::err;
+use crate::model::platform::Platform;
+use crate::sync::lock::{self, LockMode};
+use crate::sync::store_lock::LockWait;
+use crate::sync::SyncOptions;
 
 const DEFAULT_CONFIG_PATH: &str = ".tool.toml";
 
@@ -31,8 +39,43 @@ pub fn run() {
             true => print_default_path(),
             false => config::template::generate_default_config(),
         },
-        Command::Sync { tool } => sync::sync_from_path(config_path, tool, cli.proxy),
-        Command::Install { name } => install::install(config_path, name, cli.proxy),
+        Command::Sync {
+            tool,
+            update,
+            locked,
+            frozen,
+            dry_run,
+            no_wait,
+            verbose,
+            target_os,
+            target_arch,
+        } => {
+            let options = SyncOptions {
+                lock_path: Some(lock::lock_path(&config_path)),
+                lock_mode: LockMode::from_flags(update, locked, frozen),
+                dry_run,
+                lock_wait: match no_wait {
+                    true => LockWait::NoWait,
+                    false => LockWait::Wait,
+                },
+                verbose,
+                platform: Platform::target(target_os, target_arch),
+            };
+            sync::sync_from_path(config_path, tool, cli.proxy, options)
+        }
+        Command::Install {
+            name,
+            target_os,
+            target_arch,
+        } => {
+            let platform = Platform::target(target_os, target_arch);
+            install::install(config_path, name, cli.proxy, platform)
+        }
+        Command::List => list::list(config_path, cli.proxy),
+        Command::Outdated => outdated::outdated(config_path, cli.proxy),
+        Command::Uninstall { name } => uninstall::uninstall(config_path, name, cli.proxy),
+        Command::Prune => uninstall::prune(config_path, cli.proxy),
+        Command::Rollback { name } => rollback::rollback(config_path, name, cli.proxy),
     }
 }
 
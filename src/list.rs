@@ -0,0 +1,153 @@
+use indicatif::HumanDuration;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::schema::Config;
+use crate::config::toml;
+use crate::infra::table::fmt_table;
+use crate::sync::state::{InstalledTool, StoreState};
+
+/// List all tools installed in the store directory
+pub fn list(config_path: PathBuf, proxy: Option<String>) {
+    toml::with_parsed_file(config_path, proxy, list_tools)
+}
+
+fn list_tools(config: Config) {
+    let state = match config.existing_store_directory() {
+        Some(store_directory) => StoreState::read(&store_directory),
+        None => StoreState::default(),
+    };
+
+    if state.tools.is_empty() {
+        eprintln!(
+            "No tools installed by 'tool-sync' in: {}",
+            config.store_directory().display()
+        );
+        return;
+    }
+
+    let rows = list_rows(&state, |name| config.tools.contains_key(name));
+
+    println!(
+        "{}",
+        fmt_table(
+            &["tool", "tag", "repo", "asset", "installed", "in config"],
+            &rows
+        )
+    );
+}
+
+fn list_rows(state: &StoreState, in_config: impl Fn(&str) -> bool) -> Vec<Vec<String>> {
+    state
+        .tools
+        .iter()
+        .map(|(name, installed)| {
+            vec![
+                name.clone(),
+                installed.tag.clone(),
+                fmt_repo(installed),
+                installed.asset_name.clone(),
+                fmt_installed_at(installed.installed_at),
+                fmt_in_config(in_config(name)),
+            ]
+        })
+        .collect()
+}
+
+fn fmt_repo(installed: &InstalledTool) -> String {
+    if installed.owner.is_empty() || installed.repo.is_empty() {
+        String::from("unknown")
+    } else {
+        format!("{}/{}", installed.owner, installed.repo)
+    }
+}
+
+fn fmt_installed_at(installed_at: u64) -> String {
+    if installed_at == 0 {
+        return String::from("unknown");
+    }
+
+    let installed_time = UNIX_EPOCH + Duration::from_secs(installed_at);
+
+    match SystemTime::now().duration_since(installed_time) {
+        Ok(elapsed) => format!("{} ago", HumanDuration(elapsed)),
+        Err(_) => String::from("just now"),
+    }
+}
+
+fn fmt_in_config(in_config: bool) -> String {
+    if in_config {
+        String::from("yes")
+    } else {
+        String::from("no")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    fn installed_ripgrep() -> InstalledTool {
+        InstalledTool {
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            tag: String::from("13.0.0"),
+            asset_id: 42,
+            asset_name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            installed_at: 1666000000,
+            previous: vec![],
+            sha256: BTreeMap::from([(String::from("rg"), String::from("abcdef"))]),
+        }
+    }
+
+    #[test]
+    fn rows_of_installed_tools() {
+        let mut ripgrep = installed_ripgrep();
+        ripgrep.installed_at = 0;
+
+        let mut fd = installed_ripgrep();
+        fd.owner = String::new();
+        fd.tag = String::from("v8.5.3");
+        fd.asset_name = String::from("fd-v8.5.3-x86_64-unknown-linux-musl.tar.gz");
+        fd.installed_at = 0;
+
+        let state = StoreState {
+            tools: BTreeMap::from([(String::from("ripgrep"), ripgrep), (String::from("fd"), fd)]),
+        };
+
+        let rows = list_rows(&state, |name| name == "ripgrep");
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "fd",
+                    "v8.5.3",
+                    "unknown",
+                    "fd-v8.5.3-x86_64-unknown-linux-musl.tar.gz",
+                    "unknown",
+                    "no"
+                ],
+                vec![
+                    "ripgrep",
+                    "13.0.0",
+                    "BurntSushi/ripgrep",
+                    "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz",
+                    "unknown",
+                    "yes"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn installed_at_in_the_past() {
+        assert_eq!(fmt_installed_at(0), "unknown");
+        assert_eq!(fmt_installed_at(u64::MAX / 2), "just now");
+        assert!(fmt_installed_at(1666000000).ends_with(" ago"));
+    }
+}
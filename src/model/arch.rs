@@ -0,0 +1,76 @@
+use std::env;
+use std::fmt::{Display, Formatter};
+
+/// CPU architecture of the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Arch {
+    X86_64,
+    Aarch64,
+}
+
+/// Return the current CPU architecture where the 'tool-sync' is running
+///
+/// !!! WARNING !!! Like `get_current_os`, this function uses the architecture
+/// 'tool-sync' was compiled for. A x86_64 executable running on Apple Silicon
+/// via Rosetta reports x86_64.
+pub fn get_current_arch() -> Arch {
+    match env::consts::ARCH {
+        "aarch64" => Arch::Aarch64,
+        _ => Arch::X86_64,
+    }
+}
+
+impl Arch {
+    /// Parse the architecture name used in the config. Common aliases from
+    /// asset names (e.g. "amd64" or "arm64") are accepted as well.
+    pub fn from_name(name: &str) -> Option<Arch> {
+        match name {
+            "x86_64" | "amd64" | "x64" => Some(Arch::X86_64),
+            "aarch64" | "arm64" => Some(Arch::Aarch64),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Arch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::X86_64 => {
+                write!(f, "x86_64")
+            }
+            Self::Aarch64 => {
+                write!(f, "aarch64")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_arch() {
+        let arch = get_current_arch();
+
+        if cfg!(target_arch = "aarch64") {
+            assert_eq!(arch, Arch::Aarch64);
+        } else {
+            assert_eq!(arch, Arch::X86_64);
+        }
+    }
+
+    #[test]
+    fn arch_from_name() {
+        assert_eq!(Arch::from_name("x86_64"), Some(Arch::X86_64));
+        assert_eq!(Arch::from_name("amd64"), Some(Arch::X86_64));
+        assert_eq!(Arch::from_name("arm64"), Some(Arch::Aarch64));
+        assert_eq!(Arch::from_name("riscv64"), None);
+    }
+
+    #[test]
+    fn arch_display() {
+        assert_eq!(Arch::X86_64.to_string(), String::from("x86_64"));
+        assert_eq!(Arch::Aarch64.to_string(), String::from("aarch64"));
+    }
+}
@@ -1,33 +1,163 @@
-use crate::model::os::{get_current_os, OS};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::model::arch::Arch;
+use crate::model::os::OS;
+use crate::model::release::{Asset, AssetError};
+use crate::model::variables::Variables;
 
 /// Part of the name for each OS to identify proper asset
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AssetName {
-    pub linux: Option<String>,
-    pub macos: Option<String>,
-    pub windows: Option<String>,
+    pub linux: Option<AssetPattern>,
+    pub macos: Option<AssetPattern>,
+    pub windows: Option<AssetPattern>,
+
+    /// Selectors for a specific CPU architecture (e.g. `asset_name.linux-aarch64`).
+    /// They win over the selectors for the whole OS above.
+    pub arch_specific: BTreeMap<(OS, Arch), AssetPattern>,
+}
+
+/// How the asset name from the config is matched against the names of release
+/// assets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetPattern {
+    /// Plain string, e.g. `asset_name.linux = "x86_64-unknown-linux-musl"`.
+    /// Matches all assets containing this string.
+    Substring(String),
+
+    /// Glob, e.g. `asset_name.linux = { glob = "*x86_64*linux-musl.tar.gz" }`.
+    /// Matches the full asset name.
+    Glob(String),
+
+    /// Regular expression, e.g. `asset_name.linux = { regex = "musl\\.tar\\.gz$" }`.
+    /// Matches assets containing a match of the expression.
+    Regex(String),
+}
+
+impl Display for AssetPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetPattern::Substring(substring) => write!(f, "{}", substring),
+            AssetPattern::Glob(glob) => write!(f, "{{ glob = {:?} }}", glob),
+            AssetPattern::Regex(regex) => write!(f, "{{ regex = {:?} }}", regex),
+        }
+    }
+}
+
+/// Predicate on asset names compiled from `AssetPattern`
+pub type AssetMatcher<'a> = Box<dyn Fn(&str) -> bool + 'a>;
+
+impl AssetPattern {
+    /// Replace placeholders like `{version}` or `{libc}` with values of the
+    /// variables
+    pub fn expand(&self, variables: &Variables) -> AssetPattern {
+        match self {
+            AssetPattern::Substring(substring) => {
+                AssetPattern::Substring(variables.expand(substring))
+            }
+            AssetPattern::Glob(glob) => AssetPattern::Glob(variables.expand(glob)),
+            AssetPattern::Regex(regex) => AssetPattern::Regex(variables.expand(regex)),
+        }
+    }
+
+    /// Compile the pattern into a predicate on asset names
+    pub fn matcher(&self) -> Result<AssetMatcher<'_>, AssetError> {
+        match self {
+            AssetPattern::Substring(substring) => {
+                Ok(Box::new(move |name| name.contains(substring.as_str())))
+            }
+            AssetPattern::Glob(glob) => match glob::Pattern::new(glob) {
+                Ok(pattern) => Ok(Box::new(move |name| pattern.matches(name))),
+                Err(e) => Err(AssetError::InvalidPattern {
+                    pattern: self.to_string(),
+                    error: e.to_string(),
+                }),
+            },
+            AssetPattern::Regex(regex) => match regex::Regex::new(regex) {
+                Ok(regex) => Ok(Box::new(move |name| regex.is_match(name))),
+                Err(e) => Err(AssetError::InvalidPattern {
+                    pattern: self.to_string(),
+                    error: e.to_string(),
+                }),
+            },
+        }
+    }
 }
 
 impl AssetName {
-    /// Get the current OS and extract the corresponding name
-    /// of the downloaded tool
-    pub fn get_name_by_os(&self) -> Option<&String> {
-        match get_current_os() {
+    /// Extract the name of the downloaded tool for the given OS and
+    /// architecture, falling back to the selector for the whole OS
+    pub fn get_name(&self, os: OS, arch: Arch) -> Option<&AssetPattern> {
+        self.arch_specific
+            .get(&(os, arch))
+            .or_else(|| self.get_os_name(os))
+    }
+
+    fn get_os_name(&self, os: OS) -> Option<&AssetPattern> {
+        match os {
             OS::Windows => self.windows.as_ref(),
             OS::MacOS => self.macos.as_ref(),
             OS::Linux => self.linux.as_ref(),
         }
     }
+
+    /// Whether there's at least one selector for the OS
+    pub fn has_os(&self, os: OS) -> bool {
+        self.get_os_name(os).is_some() || self.arch_specific.keys().any(|(o, _)| *o == os)
+    }
 }
 
-/// Add .exe extension to executables on Windows
-pub fn mk_exe_name(exe_name: &str) -> String {
-    let windows_exe_name: String = format!("{exe_name}.exe");
+/// Rules for narrowing down assets when several of them match the asset name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetRules {
+    /// Substrings of preferred asset names, from the most preferred one
+    pub prefer: Vec<String>,
 
-    if cfg!(windows) {
-        windows_exe_name
-    } else {
-        exe_name.to_owned()
+    /// Assets containing any of these substrings are never selected
+    pub exclude: Vec<String>,
+}
+
+impl AssetRules {
+    /// Whether the asset name contains any of the excluded substrings
+    pub fn is_excluded(&self, asset_name: &str) -> bool {
+        self.exclude
+            .iter()
+            .any(|excluded| asset_name.contains(excluded.as_str()))
+    }
+
+    /// Narrow down assets by each preference in order until a single asset is
+    /// left. Preferences matching none of the assets are skipped. Returns the
+    /// remaining assets and the preferences that narrowed them down.
+    pub fn apply_preferences<'a>(&self, mut assets: Vec<&'a Asset>) -> (Vec<&'a Asset>, Vec<&str>) {
+        let mut applied = vec![];
+
+        for preferred in &self.prefer {
+            if assets.len() <= 1 {
+                break;
+            }
+
+            let matching: Vec<&Asset> = assets
+                .iter()
+                .copied()
+                .filter(|asset| asset.name.contains(preferred.as_str()))
+                .collect();
+
+            if !matching.is_empty() && matching.len() < assets.len() {
+                assets = matching;
+                applied.push(preferred.as_str());
+            }
+        }
+
+        (assets, applied)
+    }
+}
+
+/// Add .exe extension to executables for Windows
+pub fn mk_exe_name(exe_name: &str, os: OS) -> String {
+    match os {
+        OS::Windows => format!("{exe_name}.exe"),
+        OS::MacOS | OS::Linux => exe_name.to_owned(),
     }
 }
 
@@ -35,33 +165,184 @@ pub fn mk_exe_name(exe_name: &str) -> String {
 mod tests {
     use super::*;
 
+    use crate::model::libc::Libc;
+    use crate::model::platform::Platform;
+
     #[test]
     fn exe_name() {
-        let exe_name = mk_exe_name("my-name");
-
-        if cfg!(windows) {
-            assert_eq!(exe_name, "my-name.exe");
-        } else {
-            assert_eq!(exe_name, "my-name");
-        }
+        assert_eq!(mk_exe_name("my-name", OS::Windows), "my-name.exe");
+        assert_eq!(mk_exe_name("my-name", OS::MacOS), "my-name");
+        assert_eq!(mk_exe_name("my-name", OS::Linux), "my-name");
     }
 
     #[test]
     fn asset_name() {
         let asset_name = AssetName {
-            linux: Some(String::from("oh-my-zsh")),
-            macos: Some(String::from("fish")),
-            windows: Some(String::from("powershell")),
+            linux: Some(AssetPattern::Substring(String::from("oh-my-zsh"))),
+            macos: Some(AssetPattern::Substring(String::from("fish"))),
+            windows: Some(AssetPattern::Substring(String::from("powershell"))),
+            arch_specific: BTreeMap::new(),
         };
 
-        let name = asset_name.get_name_by_os();
+        assert_eq!(
+            asset_name.get_name(OS::Windows, Arch::X86_64),
+            Some(&AssetPattern::Substring(String::from("powershell")))
+        );
+        assert_eq!(
+            asset_name.get_name(OS::MacOS, Arch::X86_64),
+            Some(&AssetPattern::Substring(String::from("fish")))
+        );
+        assert_eq!(
+            asset_name.get_name(OS::Linux, Arch::X86_64),
+            Some(&AssetPattern::Substring(String::from("oh-my-zsh")))
+        );
+    }
+
+    #[test]
+    fn arch_specific_asset_name() {
+        let asset_name = AssetName {
+            linux: Some(AssetPattern::Substring(String::from("x86_64-linux"))),
+            macos: None,
+            windows: None,
+            arch_specific: BTreeMap::from([(
+                (OS::Linux, Arch::Aarch64),
+                AssetPattern::Substring(String::from("aarch64-linux")),
+            )]),
+        };
 
-        if cfg!(target_os = "windows") {
-            assert_eq!(name, Some(&String::from("powershell")));
-        } else if cfg!(target_os = "macos") {
-            assert_eq!(name, Some(&String::from("fish")));
-        } else {
-            assert_eq!(name, Some(&String::from("oh-my-zsh")));
+        assert_eq!(
+            asset_name.get_name(OS::Linux, Arch::Aarch64),
+            Some(&AssetPattern::Substring(String::from("aarch64-linux")))
+        );
+        assert_eq!(
+            asset_name.get_name(OS::Linux, Arch::X86_64),
+            Some(&AssetPattern::Substring(String::from("x86_64-linux")))
+        );
+        assert_eq!(asset_name.get_name(OS::MacOS, Arch::Aarch64), None);
+    }
+
+    #[test]
+    fn os_asset_name_on_aarch64() {
+        let asset_name = AssetName {
+            linux: Some(AssetPattern::Substring(String::from(
+                "aarch64-unknown-linux-gnu",
+            ))),
+            macos: None,
+            windows: None,
+            arch_specific: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            asset_name.get_name(OS::Linux, Arch::Aarch64),
+            Some(&AssetPattern::Substring(String::from(
+                "aarch64-unknown-linux-gnu"
+            )))
+        );
+    }
+
+    fn matches(pattern: AssetPattern, name: &str) -> bool {
+        pattern.matcher().expect("This should not fail")(name)
+    }
+
+    #[test]
+    fn substring_pattern() {
+        let pattern = AssetPattern::Substring(String::from("linux-musl"));
+
+        assert!(matches(pattern.clone(), "rg-x86_64-linux-musl.tar.gz"));
+        assert!(matches(
+            pattern.clone(),
+            "rg-x86_64-linux-musl.tar.gz.sha256"
+        ));
+        assert!(!matches(pattern, "rg-x86_64-apple-darwin.tar.gz"));
+    }
+
+    #[test]
+    fn glob_pattern() {
+        let pattern = AssetPattern::Glob(String::from("*x86_64*linux-musl.tar.gz"));
+
+        assert!(matches(pattern.clone(), "rg-x86_64-linux-musl.tar.gz"));
+        assert!(!matches(pattern, "rg-x86_64-linux-musl.tar.gz.sha256"));
+    }
+
+    #[test]
+    fn regex_pattern() {
+        let pattern = AssetPattern::Regex(String::from(r"x86_64.*musl\.tar\.gz$"));
+
+        assert!(matches(pattern.clone(), "rg-x86_64-linux-musl.tar.gz"));
+        assert!(!matches(pattern, "rg-x86_64-linux-musl.tar.gz.sig"));
+    }
+
+    #[test]
+    fn invalid_regex_pattern() {
+        let pattern = AssetPattern::Regex(String::from("musl("));
+
+        assert!(matches!(
+            pattern.matcher(),
+            Err(AssetError::InvalidPattern { .. })
+        ));
+    }
+
+    fn asset(name: &str) -> Asset {
+        Asset {
+            id: 1,
+            name: String::from(name),
+            size: 100,
+            updated_at: None,
         }
     }
+
+    #[test]
+    fn excluded_assets() {
+        let rules = AssetRules {
+            prefer: vec![],
+            exclude: vec![String::from(".sha256"), String::from(".deb")],
+        };
+
+        assert!(rules.is_excluded("rg-x86_64-linux-musl.tar.gz.sha256"));
+        assert!(rules.is_excluded("rg_13.0.0_amd64.deb"));
+        assert!(!rules.is_excluded("rg-x86_64-linux-musl.tar.gz"));
+    }
+
+    #[test]
+    fn preferences_applied_in_order() {
+        let rules = AssetRules {
+            prefer: vec![
+                String::from("windows"),
+                String::from("musl"),
+                String::from(".tar.gz"),
+            ],
+            exclude: vec![],
+        };
+
+        let assets = [
+            asset("rg-x86_64-linux-gnu.tar.gz"),
+            asset("rg-x86_64-linux-musl.zip"),
+            asset("rg-x86_64-linux-musl.tar.gz"),
+        ];
+
+        let (remaining, applied) = rules.apply_preferences(assets.iter().collect());
+
+        assert_eq!(remaining, vec![&assets[2]]);
+        assert_eq!(applied, vec!["musl", ".tar.gz"]);
+    }
+
+    #[test]
+    fn expand_pattern() {
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::X86_64,
+            libc: Some(Libc::Musl),
+        };
+        let variables = Variables::new("ripgrep", "13.0.0", &platform);
+
+        assert_eq!(
+            AssetPattern::Substring(String::from("{version}-x86_64-unknown-linux-{libc}"))
+                .expand(&variables),
+            AssetPattern::Substring(String::from("13.0.0-x86_64-unknown-linux-musl"))
+        );
+        assert_eq!(
+            AssetPattern::Regex(String::from("linux-{libc}-\\d{2}")).expand(&variables),
+            AssetPattern::Regex(String::from("linux-musl-\\d{2}"))
+        );
+    }
 }
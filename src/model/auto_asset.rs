@@ -0,0 +1,426 @@
+//! This file contains heuristics for selecting an asset automatically when the
+//! config doesn't specify 'asset_name' for the current OS. Most projects name
+//! their assets after the target triple or similar (e.g.
+//! "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz") so OS, architecture and
+//! libc can be guessed from the name.
+
+use crate::model::arch::Arch;
+use crate::model::asset_name::AssetRules;
+use crate::model::libc::Libc;
+use crate::model::os::OS;
+use crate::model::platform::Platform;
+use crate::model::release::{Asset, AssetError};
+
+/// Files published next to the actual assets that are never executables
+const IGNORED_EXTENSIONS: &[&str] = &[
+    ".sha256",
+    ".sha256sum",
+    ".sha512",
+    ".sha512sum",
+    ".md5",
+    ".sig",
+    ".asc",
+    ".pem",
+    ".sbom",
+    ".txt",
+    ".json",
+    ".deb",
+    ".rpm",
+    ".apk",
+    ".msi",
+    ".pkg",
+    ".dmg",
+];
+
+/// Asset extensions 'tool-sync' can unpack
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2", ".tbz2", ".zip", ".exe",
+];
+
+/// Architectures 'tool-sync' never installs
+const OTHER_ARCH_KEYWORDS: &[&str] = &[
+    "i386", "i686", "386", "x86", "arm", "armv6", "armv7", "armv7l", "armhf", "ppc64", "ppc64le",
+    "s390x", "riscv64", "mips", "mipsel", "mips64",
+];
+
+/// Selected asset together with a human-readable explanation of the choice
+#[derive(Debug, PartialEq, Eq)]
+pub struct SelectedAsset {
+    pub asset: Asset,
+    pub explanation: String,
+}
+
+/// Why an asset fits the current machine
+#[derive(Debug, PartialEq, Eq)]
+struct Score {
+    points: u32,
+    reasons: Vec<&'static str>,
+}
+
+/// Select the single asset that fits the OS, architecture and libc (only on
+/// Linux) of the platform best. Excluded assets are ignored and preferences
+/// from `rules` resolve ties.
+pub fn select_auto_asset(
+    assets: &[Asset],
+    platform: &Platform,
+    rules: &AssetRules,
+) -> Result<SelectedAsset, AssetError> {
+    let Platform { os, arch, libc } = *platform;
+    let is_ignored = |name: &str| is_ignored(name) || rules.is_excluded(name);
+    let ignored_count = assets
+        .iter()
+        .filter(|asset| is_ignored(&asset.name))
+        .count();
+
+    let mut candidates: Vec<(&Asset, Score)> = assets
+        .iter()
+        .filter(|asset| !is_ignored(&asset.name))
+        .filter_map(|asset| score_asset(&asset.name, os, arch, libc).map(|score| (asset, score)))
+        .collect();
+
+    // Report glibc assets on musl systems instead of not finding any asset
+    if let (true, Some(libc)) = (candidates.is_empty(), libc) {
+        if let Some(asset) = assets
+            .iter()
+            .filter(|asset| !is_ignored(&asset.name))
+            .find(|asset| score_asset(&asset.name, os, arch, None).is_some())
+        {
+            return Err(AssetError::IncompatibleLibc {
+                asset: asset.name.clone(),
+                libc,
+            });
+        }
+    }
+
+    candidates.sort_by_key(|(_, score)| std::cmp::Reverse(score.points));
+
+    let (best_score, best) = match candidates.first() {
+        None => return Err(AssetError::OsSelectorUnknown(*platform)),
+        Some((_, score)) => (
+            score,
+            candidates
+                .iter()
+                .filter(|(_, other)| other.points == score.points)
+                .map(|(asset, _)| *asset)
+                .collect::<Vec<&Asset>>(),
+        ),
+    };
+
+    let (best, preferred) = rules.apply_preferences(best);
+
+    match best.as_slice() {
+        [asset] => {
+            let mut reasons = best_score.reasons.clone();
+            let preferred: Vec<String> = preferred
+                .iter()
+                .map(|preference| format!("preferred '{}'", preference))
+                .collect();
+            reasons.extend(preferred.iter().map(|reason| reason.as_str()));
+
+            let explanation = format!(
+                "Automatically selected '{}' for {}: {} (candidates: {}, ignored files: {})",
+                asset.name,
+                platform,
+                reasons.join(", "),
+                candidates.len(),
+                ignored_count,
+            );
+
+            Ok(SelectedAsset {
+                asset: (*asset).clone(),
+                explanation,
+            })
+        }
+        _ => Err(AssetError::MultipleFound(
+            best.iter().map(|asset| asset.name.clone()).collect(),
+        )),
+    }
+}
+
+fn is_ignored(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("checksum") || IGNORED_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Score the asset name or return `None` if the asset doesn't fit at all
+fn score_asset(name: &str, os: OS, arch: Arch, libc: Option<Libc>) -> Option<Score> {
+    let name = name.to_lowercase();
+    let extension = SUPPORTED_EXTENSIONS
+        .iter()
+        .find(|ext| name.ends_with(*ext))?;
+
+    // "x86_64" and "x86-64" would be split into two separate words otherwise
+    let normalized = name.replace("x86_64", "amd64").replace("x86-64", "amd64");
+    let words: Vec<&str> = normalized
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let has_word = |keywords: &[&str]| words.iter().any(|word| keywords.contains(word));
+
+    let mut score = Score {
+        points: 0,
+        reasons: vec![],
+    };
+
+    // OS must be mentioned explicitly, other OSes rule the asset out
+    let asset_os = OS::ALL
+        .into_iter()
+        .filter(|asset_os| has_word(os_keywords(*asset_os)))
+        .collect::<Vec<OS>>();
+    let is_windows_exe = os == OS::Windows && *extension == ".exe";
+    if !(asset_os == vec![os] || (asset_os.is_empty() && is_windows_exe)) {
+        return None;
+    }
+    score.points += 8;
+    score.reasons.push("os");
+
+    if has_word(arch_keywords(arch)) {
+        score.points += 4;
+        score.reasons.push("arch");
+    } else if os == OS::MacOS && has_word(&["universal"]) {
+        score.points += 3;
+        score.reasons.push("universal binary");
+    } else if has_word(OTHER_ARCH_KEYWORDS) || has_word(arch_keywords(other_arch(arch))) {
+        return None;
+    } else {
+        score.points += 1;
+        score.reasons.push("no arch");
+    }
+
+    // Static musl executables run everywhere while glibc ones don't run on
+    // musl systems like Alpine
+    if os == OS::Linux {
+        match (Libc::from_asset_name(&name), libc) {
+            (Some(Libc::Musl), _) => {
+                score.points += 2;
+                score.reasons.push("musl");
+            }
+            (Some(Libc::Gnu), Some(Libc::Musl)) => return None,
+            (Some(Libc::Gnu), _) => {
+                score.points += 1;
+                score.reasons.push("gnu");
+            }
+            (None, _) => {}
+        }
+    }
+
+    let preferred_extension = match os {
+        OS::Windows => ".zip",
+        OS::MacOS | OS::Linux => ".tar.gz",
+    };
+    if *extension == preferred_extension {
+        score.points += 1;
+    }
+    score.reasons.push(extension.trim_start_matches('.'));
+
+    Some(score)
+}
+
+fn os_keywords(os: OS) -> &'static [&'static str] {
+    match os {
+        OS::Linux => &["linux"],
+        OS::MacOS => &["darwin", "macos", "apple", "osx", "mac"],
+        OS::Windows => &["windows", "win", "win32", "win64", "msvc"],
+    }
+}
+
+fn arch_keywords(arch: Arch) -> &'static [&'static str] {
+    match arch {
+        Arch::X86_64 => &["amd64", "x64"],
+        Arch::Aarch64 => &["aarch64", "arm64"],
+    }
+}
+
+fn other_arch(arch: Arch) -> Arch {
+    match arch {
+        Arch::X86_64 => Arch::Aarch64,
+        Arch::Aarch64 => Arch::X86_64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets(names: &[&str]) -> Vec<Asset> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| Asset {
+                id: id as u32,
+                name: name.to_string(),
+                size: 100,
+                updated_at: None,
+            })
+            .collect()
+    }
+
+    fn platform(os: OS, arch: Arch) -> Platform {
+        Platform {
+            os,
+            arch,
+            libc: None,
+        }
+    }
+
+    fn selected_name(names: &[&str], os: OS, arch: Arch) -> Result<String, AssetError> {
+        select_auto_asset(&assets(names), &platform(os, arch), &AssetRules::default())
+            .map(|auto| auto.asset.name)
+    }
+
+    const RIPGREP_ASSETS: &[&str] = &[
+        "ripgrep-13.0.0-arm-unknown-linux-gnueabihf.tar.gz",
+        "ripgrep-13.0.0-i686-pc-windows-msvc.zip",
+        "ripgrep-13.0.0-x86_64-apple-darwin.tar.gz",
+        "ripgrep-13.0.0-x86_64-apple-darwin.tar.gz.sha256",
+        "ripgrep-13.0.0-x86_64-pc-windows-gnu.zip",
+        "ripgrep-13.0.0-x86_64-pc-windows-msvc.zip",
+        "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz",
+        "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+        "ripgrep_13.0.0_amd64.deb",
+    ];
+
+    #[test]
+    fn select_linux_musl() {
+        assert_eq!(
+            selected_name(RIPGREP_ASSETS, OS::Linux, Arch::X86_64),
+            Ok(String::from(
+                "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"
+            ))
+        );
+    }
+
+    #[test]
+    fn select_macos() {
+        assert_eq!(
+            selected_name(RIPGREP_ASSETS, OS::MacOS, Arch::X86_64),
+            Ok(String::from("ripgrep-13.0.0-x86_64-apple-darwin.tar.gz"))
+        );
+    }
+
+    #[test]
+    fn ambiguous_windows() {
+        assert_eq!(
+            selected_name(RIPGREP_ASSETS, OS::Windows, Arch::X86_64),
+            Err(AssetError::MultipleFound(vec![
+                String::from("ripgrep-13.0.0-x86_64-pc-windows-gnu.zip"),
+                String::from("ripgrep-13.0.0-x86_64-pc-windows-msvc.zip"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn no_asset_for_arch() {
+        assert_eq!(
+            selected_name(RIPGREP_ASSETS, OS::Linux, Arch::Aarch64),
+            Err(AssetError::OsSelectorUnknown(platform(
+                OS::Linux,
+                Arch::Aarch64
+            )))
+        );
+    }
+
+    #[test]
+    fn prefer_arch_over_no_arch() {
+        let names = [
+            "tool_Linux_arm64.tar.gz",
+            "tool_Linux_x86_64.tar.gz",
+            "tool_Darwin_all.tar.gz",
+        ];
+
+        assert_eq!(
+            selected_name(&names, OS::Linux, Arch::Aarch64),
+            Ok(String::from("tool_Linux_arm64.tar.gz"))
+        );
+    }
+
+    #[test]
+    fn select_tar_xz() {
+        let names = [
+            "shellcheck-v0.9.0.darwin.x86_64.tar.xz",
+            "shellcheck-v0.9.0.linux.aarch64.tar.xz",
+            "shellcheck-v0.9.0.linux.x86_64.tar.xz",
+            "shellcheck-v0.9.0.zip",
+        ];
+
+        assert_eq!(
+            selected_name(&names, OS::Linux, Arch::X86_64),
+            Ok(String::from("shellcheck-v0.9.0.linux.x86_64.tar.xz"))
+        );
+    }
+
+    #[test]
+    fn explanation_mentions_reasons() {
+        let auto = select_auto_asset(
+            &assets(RIPGREP_ASSETS),
+            &platform(OS::Linux, Arch::X86_64),
+            &AssetRules::default(),
+        )
+        .expect("This should not fail");
+
+        assert_eq!(
+            auto.explanation,
+            "Automatically selected 'ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz' for linux-x86_64: os, arch, musl, tar.gz (candidates: 1, ignored files: 3)"
+        );
+    }
+
+    #[test]
+    fn preference_resolves_tie() {
+        let rules = AssetRules {
+            prefer: vec![String::from("msvc")],
+            exclude: vec![],
+        };
+
+        let auto = select_auto_asset(
+            &assets(RIPGREP_ASSETS),
+            &platform(OS::Windows, Arch::X86_64),
+            &rules,
+        )
+        .expect("This should not fail");
+
+        assert_eq!(auto.asset.name, "ripgrep-13.0.0-x86_64-pc-windows-msvc.zip");
+        assert!(auto.explanation.contains("preferred 'msvc'"));
+    }
+
+    #[test]
+    fn exclusion_resolves_tie() {
+        let rules = AssetRules {
+            prefer: vec![],
+            exclude: vec![String::from("-gnu")],
+        };
+
+        let auto = select_auto_asset(
+            &assets(RIPGREP_ASSETS),
+            &platform(OS::Windows, Arch::X86_64),
+            &rules,
+        )
+        .expect("This should not fail");
+
+        assert_eq!(auto.asset.name, "ripgrep-13.0.0-x86_64-pc-windows-msvc.zip");
+    }
+
+    #[test]
+    fn glibc_asset_on_musl() {
+        let names = [
+            "difft-x86_64-apple-darwin.tar.gz",
+            "difft-x86_64-unknown-linux-gnu.tar.gz",
+        ];
+
+        assert_eq!(
+            select_auto_asset(
+                &assets(&names),
+                &Platform {
+                    os: OS::Linux,
+                    arch: Arch::X86_64,
+                    libc: Some(Libc::Musl),
+                },
+                &AssetRules::default(),
+            )
+            .map(|auto| auto.asset.name),
+            Err(AssetError::IncompatibleLibc {
+                asset: String::from("difft-x86_64-unknown-linux-gnu.tar.gz"),
+                libc: Libc::Musl,
+            })
+        );
+    }
+}
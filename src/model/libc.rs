@@ -0,0 +1,123 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use crate::model::os::{get_current_os, OS};
+
+/// C standard library of a Linux system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libc {
+    Gnu,
+    Musl,
+}
+
+/// Directories with the dynamic loader of glibc or musl
+const LOADER_DIRECTORIES: [&str; 3] = ["/lib", "/lib64", "/usr/lib"];
+
+/// Return the libc of the system where 'tool-sync' is running or `None` on
+/// non-Linux systems.
+///
+/// Unlike `get_current_os`, the libc is detected at runtime: the same static
+/// 'tool-sync' executable runs both on Debian and on Alpine.
+pub fn get_current_libc() -> Option<Libc> {
+    match get_current_os() {
+        OS::Linux => {
+            let directories: Vec<&Path> = LOADER_DIRECTORIES.iter().map(Path::new).collect();
+            Some(detect_libc(&directories))
+        }
+        OS::MacOS | OS::Windows => None,
+    }
+}
+
+/// A system uses musl when it has the musl dynamic loader (e.g.
+/// '/lib/ld-musl-x86_64.so.1') and no glibc one. Alpine with 'gcompat' has
+/// both and runs glibc executables.
+fn detect_libc(directories: &[&Path]) -> Libc {
+    let has_loader = |prefix: &str| {
+        directories.iter().any(|directory| {
+            fs::read_dir(directory).is_ok_and(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .any(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+            })
+        })
+    };
+
+    if has_loader("ld-musl-") && !has_loader("ld-linux") {
+        Libc::Musl
+    } else {
+        Libc::Gnu
+    }
+}
+
+impl Libc {
+    /// Guess the libc an asset is built for from its name, e.g.
+    /// "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"
+    pub fn from_asset_name(asset_name: &str) -> Option<Libc> {
+        let asset_name = asset_name.to_lowercase();
+        let words: Vec<&str> = asset_name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .collect();
+
+        if words.iter().any(|word| word.starts_with("musl")) {
+            Some(Libc::Musl)
+        } else if words
+            .iter()
+            .any(|word| word.starts_with("gnu") || *word == "glibc")
+        {
+            Some(Libc::Gnu)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Libc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gnu => {
+                write!(f, "gnu")
+            }
+            Self::Musl => {
+                write!(f, "musl")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use tempdir::TempDir;
+
+    #[test]
+    fn libc_from_asset_name() {
+        assert_eq!(
+            Libc::from_asset_name("rg-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            Some(Libc::Musl)
+        );
+        assert_eq!(
+            Libc::from_asset_name("difft-x86_64-unknown-linux-gnu.tar.gz"),
+            Some(Libc::Gnu)
+        );
+        assert_eq!(
+            Libc::from_asset_name("rg-13.0.0-arm-unknown-linux-gnueabihf.tar.gz"),
+            Some(Libc::Gnu)
+        );
+        assert_eq!(Libc::from_asset_name("tool_Linux_x86_64.tar.gz"), None);
+    }
+
+    #[test]
+    fn detect_musl() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        File::create(tmp_dir.path().join("ld-musl-x86_64.so.1")).expect("This should not fail");
+
+        assert_eq!(detect_libc(&[tmp_dir.path()]), Libc::Musl);
+
+        File::create(tmp_dir.path().join("ld-linux-x86-64.so.2")).expect("This should not fail");
+
+        assert_eq!(detect_libc(&[tmp_dir.path()]), Libc::Gnu);
+    }
+}
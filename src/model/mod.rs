@@ -1,5 +1,10 @@
+pub mod arch;
 pub mod asset_name;
+pub mod auto_asset;
+pub mod libc;
 pub mod os;
+pub mod platform;
 pub mod release;
 pub mod repo;
 pub mod tool;
+pub mod variables;
@@ -1,7 +1,8 @@
 use std::env;
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum OS {
     Windows,
     MacOS,
@@ -21,6 +22,14 @@ pub fn get_current_os() -> OS {
     }
 }
 
+impl OS {
+    pub const ALL: [OS; 3] = [OS::Windows, OS::MacOS, OS::Linux];
+
+    pub fn from_name(name: &str) -> Option<OS> {
+        OS::ALL.into_iter().find(|os| os.to_string() == name)
+    }
+}
+
 impl Display for OS {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -54,6 +63,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn os_from_name() {
+        assert_eq!(OS::from_name("linux"), Some(OS::Linux));
+        assert_eq!(OS::from_name("macos"), Some(OS::MacOS));
+        assert_eq!(OS::from_name("darwin"), None);
+    }
+
     #[test]
     fn os_display() {
         assert_eq!(OS::Windows.to_string(), String::from("windows"));
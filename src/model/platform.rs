@@ -0,0 +1,107 @@
+use std::fmt::{Display, Formatter};
+
+use crate::model::arch::{get_current_arch, Arch};
+use crate::model::libc::{get_current_libc, Libc};
+use crate::model::os::{get_current_os, OS};
+
+/// The machine tools are installed for. It's the current machine unless
+/// `--target-os` or `--target-arch` are specified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    pub os: OS,
+    pub arch: Arch,
+
+    /// Only known for the current Linux machine
+    pub libc: Option<Libc>,
+}
+
+impl Platform {
+    pub fn current() -> Platform {
+        Platform {
+            os: get_current_os(),
+            arch: get_current_arch(),
+            libc: get_current_libc(),
+        }
+    }
+
+    /// Override the OS or the architecture of the current machine. The libc
+    /// of a machine with another OS can't be detected, so only targets with
+    /// the current OS keep the current libc.
+    pub fn target(os: Option<OS>, arch: Option<Arch>) -> Platform {
+        let current = Platform::current();
+        let os = os.unwrap_or(current.os);
+
+        Platform {
+            os,
+            arch: arch.unwrap_or(current.arch),
+            libc: if os == current.os { current.libc } else { None },
+        }
+    }
+
+    /// Value of the `{libc}` placeholder. Static musl executables run on any
+    /// Linux machine so they're chosen when the libc is unknown.
+    pub fn libc_variable(&self) -> Option<Libc> {
+        match self.os {
+            OS::Linux => Some(self.libc.unwrap_or(Libc::Musl)),
+            OS::MacOS | OS::Windows => None,
+        }
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.os, self.arch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_platform() {
+        let os = match get_current_os() {
+            OS::Linux => OS::MacOS,
+            OS::MacOS | OS::Windows => OS::Linux,
+        };
+        let platform = Platform::target(Some(os), Some(Arch::Aarch64));
+
+        assert_eq!(
+            platform,
+            Platform {
+                os,
+                arch: Arch::Aarch64,
+                libc: None,
+            }
+        );
+    }
+
+    #[test]
+    fn linux_target_libc() {
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::Aarch64,
+            libc: None,
+        };
+
+        assert_eq!(platform.libc_variable(), Some(Libc::Musl));
+        assert_eq!(platform.to_string(), "linux-aarch64");
+    }
+
+    #[test]
+    fn target_current_os_keeps_libc() {
+        let current = Platform::current();
+        let platform = Platform::target(Some(current.os), Some(Arch::Aarch64));
+
+        assert_eq!(platform.libc, current.libc);
+        assert_eq!(
+            Platform::target(None, Some(Arch::Aarch64)).libc,
+            current.libc
+        );
+    }
+
+    #[test]
+    fn no_target_platform() {
+        assert_eq!(Platform::target(None, None), Platform::current());
+    }
+}
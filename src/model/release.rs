@@ -1,13 +1,59 @@
+use semver::{Version, VersionReq};
 use serde::Deserialize;
 use std::fmt::{Display, Formatter, Write};
 
 use crate::infra::err;
-use crate::model::os::get_current_os;
+use crate::model::libc::Libc;
+use crate::model::platform::Platform;
 
 #[derive(Deserialize, Debug)]
 pub struct Release {
     pub tag_name: String,
     pub assets: Vec<Asset>,
+
+    #[serde(default)]
+    pub draft: bool,
+
+    #[serde(default)]
+    pub prerelease: bool,
+}
+
+impl Release {
+    /// Semver version of the release tag, see [`parse_version`]
+    pub fn version(&self) -> Option<Version> {
+        parse_version(&self.tag_name)
+    }
+}
+
+/// Semver version of a release tag. Prefixes like "v" or "release-" are
+/// ignored and missing minor and patch numbers are treated as zeroes (e.g.
+/// "v0.22" is "0.22.0").
+pub fn parse_version(tag: &str) -> Option<Version> {
+    let version = tag.trim_start_matches(|c: char| !c.is_ascii_digit());
+
+    Version::parse(version).ok().or_else(|| {
+        let components = version.split('.').count();
+        let padding = ".0".repeat(3_usize.saturating_sub(components));
+        Version::parse(&format!("{}{}", version, padding)).ok()
+    })
+}
+
+/// Select the most recent release including pre-releases. GitHub lists
+/// releases newest first.
+pub fn select_newest_release(releases: Vec<Release>) -> Option<Release> {
+    releases.into_iter().find(|release| !release.draft)
+}
+
+/// Select the release with the highest version matching the range. Drafts,
+/// pre-releases and tags that are not versions are skipped.
+pub fn select_release(releases: Vec<Release>, range: &VersionReq) -> Option<Release> {
+    releases
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter_map(|release| release.version().map(|version| (version, release)))
+        .filter(|(version, _)| range.matches(version))
+        .max_by(|(v1, _), (v2, _)| v1.cmp(v2))
+        .map(|(_, release)| release)
 }
 
 #[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -15,33 +61,53 @@ pub struct Asset {
     pub id: u32,
     pub name: String,
     pub size: u64,
+
+    /// Moving tags like "nightly" keep the same release but update its assets
+    #[serde(default)]
+    pub updated_at: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum AssetError {
-    /// Asset name of this OS is unknown
-    OsSelectorUnknown,
+    /// Asset name of this platform is unknown and can't be guessed
+    OsSelectorUnknown(Platform),
 
     /// Asset name is not in the fetched assets
     NotFound(String),
 
     /// Multiple asset names are found
     MultipleFound(Vec<String>),
+
+    /// The glob or regex in the config can't be compiled
+    InvalidPattern { pattern: String, error: String },
+
+    /// The asset is built for glibc but the system uses another libc
+    IncompatibleLibc { asset: String, libc: Libc },
 }
 
 impl Display for AssetError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::OsSelectorUnknown => {
+            Self::OsSelectorUnknown(platform) => {
                 write!(
                     f,
-                    "Unknown asset selector for the current OS. Specify 'asset_name.{}' in the config.",
-                    get_current_os()
+                    "No asset matches {} automatically. Specify 'asset_name.{}' in the config.",
+                    platform, platform.os
                 )
             }
             Self::NotFound(asset_name) => {
                 write!(f, "No asset matching name: {}", asset_name)
             }
+            Self::InvalidPattern { pattern, error } => {
+                write!(f, "Invalid asset name pattern {}: {}", pattern, error)
+            }
+            Self::IncompatibleLibc { asset, libc } => {
+                write!(
+                    f,
+                    "Asset '{}' is built for glibc and won't run on this {} system. Specify a {} asset in 'asset_name.linux' (e.g. using {{libc}}).",
+                    asset, libc, libc
+                )
+            }
             Self::MultipleFound(assets) => {
                 let mut formatted: String = String::from("\n");
                 for asset in assets {
@@ -58,3 +124,79 @@ impl Display for AssetError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag_name: &str) -> Release {
+        Release {
+            tag_name: tag_name.to_owned(),
+            assets: vec![],
+            draft: false,
+            prerelease: false,
+        }
+    }
+
+    fn select_tag(tags: &[&str], range: &str) -> Option<String> {
+        let releases = tags.iter().map(|tag| release(tag)).collect();
+        let range = VersionReq::parse(range).expect("This should not fail");
+
+        select_release(releases, &range).map(|release| release.tag_name)
+    }
+
+    #[test]
+    fn select_newest_prerelease() {
+        let releases = vec![
+            Release {
+                draft: true,
+                ..release("v0.10.0")
+            },
+            Release {
+                prerelease: true,
+                ..release("v0.10.0-rc.1")
+            },
+            release("v0.9.5"),
+        ];
+
+        assert_eq!(
+            select_newest_release(releases).map(|release| release.tag_name),
+            Some(String::from("v0.10.0-rc.1"))
+        );
+    }
+
+    #[test]
+    fn release_version() {
+        assert_eq!(release("13.0.0").version(), Version::parse("13.0.0").ok());
+        assert_eq!(release("v0.22.1").version(), Version::parse("0.22.1").ok());
+        assert_eq!(release("v0.22").version(), Version::parse("0.22.0").ok());
+        assert_eq!(release("nightly").version(), None);
+    }
+
+    #[test]
+    fn select_highest_matching_release() {
+        let tags = ["14.0.0", "13.0.1", "13.0.0", "12.1.1"];
+
+        assert_eq!(select_tag(&tags, "^13"), Some(String::from("13.0.1")));
+        assert_eq!(select_tag(&tags, ">=12, <14"), Some(String::from("13.0.1")));
+        assert_eq!(select_tag(&tags, "^15"), None);
+    }
+
+    #[test]
+    fn select_release_skips_prereleases() {
+        let releases = vec![
+            Release {
+                prerelease: true,
+                ..release("v0.23.1")
+            },
+            release("v0.23.0"),
+            release("v0.24.0-rc.1"),
+        ];
+        let range = VersionReq::parse(">=0.22, <0.25").expect("This should not fail");
+
+        assert_eq!(
+            select_release(releases, &range).map(|release| release.tag_name),
+            Some(String::from("v0.23.0"))
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::model::tool::ToolInfoTag;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug, PartialEq, Eq)]
@@ -15,9 +16,13 @@ impl Display for RepoError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             RepoError::NotFound { owner, repo, tag } => match tag {
-                ToolInfoTag::Latest => {
+                ToolInfoTag::Latest | ToolInfoTag::Prerelease => {
                     write!(f, "The {owner}/{repo} doesn't exist or has no releases.")
                 }
+                ToolInfoTag::Range(range) => write!(
+                    f,
+                    "The {owner}/{repo} doesn't exist or has no releases matching '{range}'."
+                ),
                 _ => write!(
                     f,
                     "The {owner}/{repo} doesn't exist or {tag} was not found.",
@@ -27,3 +32,5 @@ impl Display for RepoError {
         }
     }
 }
+
+impl Error for RepoError {}
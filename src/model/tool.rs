@@ -1,9 +1,18 @@
 use super::release::Asset;
 use crate::infra::client::Client;
-use crate::model::asset_name::AssetName;
+use crate::model::asset_name::{mk_exe_name, AssetName, AssetRules};
+use crate::model::auto_asset::{select_auto_asset, SelectedAsset};
+use crate::model::libc::Libc;
+use crate::model::os::OS;
+use crate::model::platform::Platform;
 use crate::model::release::AssetError;
+use crate::model::variables::Variables;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 
+// Values of this type are short-lived results of configuring a single tool so
+// boxing 'ToolInfo' isn't worth it
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Tool {
     Known(ToolInfo),
@@ -17,6 +26,9 @@ pub enum ToolError {
 
     /// Not enough configuration to install the tool
     Invalid,
+
+    /// The 'tool' field names a tool that isn't natively supported
+    UnknownTool { name: String },
 }
 
 impl Display for ToolError {
@@ -26,26 +38,49 @@ impl Display for ToolError {
                 write!(f, "[suggestion] Perhaps you meant: '{}'?", perhaps)
             }
             ToolError::Invalid => write!(f, "[error] Not detailed enough configuration"),
+            ToolError::UnknownTool { name } => {
+                write!(f, "[error] Unknown tool '{}' in 'tool'", name)
+            }
         }
     }
 }
 
 /// Determines whether to download the latest version of a tool or a
 /// specific version of it.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ToolInfoTag {
     /// Download latest
     Latest,
+    /// Download the newest release including pre-releases
+    Prerelease,
     /// Download a specific version
     Specific(String),
+    /// Download the highest version matching a semver range (e.g. "^13")
+    Range(String),
 }
 
 const LATEST_VERSION: &str = "latest";
 
+/// Characters that start a semver range but never a git tag
+const RANGE_PREFIXES: &[char] = &['^', '~', '<', '>', '=', '*'];
+
 impl ToolInfoTag {
+    /// Parse the 'tag' field of the config. Values like "^13" or
+    /// ">=0.22, <0.24" are semver ranges, everything else is an exact tag.
+    pub fn from_config(tag: String) -> ToolInfoTag {
+        if tag.starts_with(RANGE_PREFIXES) || tag.contains(',') {
+            ToolInfoTag::Range(tag)
+        } else {
+            ToolInfoTag::Specific(tag)
+        }
+    }
+
+    /// Version part of the GitHub API URL for fetching a single release.
+    /// Pre-releases and ranges are resolved by listing all releases so they
+    /// have no URL of their own.
     pub fn to_str_version(&self) -> String {
         match self {
-            Self::Latest => LATEST_VERSION.to_owned(),
+            Self::Latest | Self::Prerelease | Self::Range(_) => LATEST_VERSION.to_owned(),
             Self::Specific(version) => format!("tags/{}", version),
         }
     }
@@ -60,41 +95,128 @@ pub struct ToolInfo {
     /// GitHub repository name
     pub repo: String,
 
-    /// Executable name inside the .tar.gz or .zip archive
-    pub exe_name: String,
+    /// Names of executables inside the .tar.gz or .zip archive
+    pub exe_names: Vec<String>,
+
+    /// Name of the first executable in the store directory
+    pub install_as: Option<String>,
+
+    /// Glob with the path to executables inside the archive
+    pub exe_path: Option<String>,
+
+    /// Install the only executable in the archive when the executable isn't
+    /// found by name
+    pub auto_exe: bool,
 
     /// Version tag
     pub tag: ToolInfoTag,
 
     /// Asset name depending on the OS
     pub asset_name: AssetName,
+
+    /// Preferred and excluded assets when several of them match
+    pub asset_rules: AssetRules,
 }
 
 impl ToolInfo {
-    /// Select an Asset from all Assets based on which Operating System is used
-    pub fn select_asset(&self, assets: &[Asset]) -> Result<Asset, AssetError> {
-        match self.asset_name.get_name_by_os() {
-            None => Err(AssetError::OsSelectorUnknown),
+    /// Select an Asset from all Assets for the given platform. Without the
+    /// asset name for the platform, the asset is guessed from asset names.
+    /// Placeholders in the asset name are expanded with `variables`.
+    pub fn select_asset(
+        &self,
+        assets: &[Asset],
+        platform: &Platform,
+        variables: &Variables,
+    ) -> Result<SelectedAsset, AssetError> {
+        match self.asset_name.get_name(platform.os, platform.arch) {
+            None => select_auto_asset(assets, platform, &self.asset_rules),
             Some(asset_name) => {
-                let mut filtered_assets = assets
+                let asset_name = asset_name.expand(variables);
+                let is_match = asset_name.matcher()?;
+                let filtered_assets = assets
                     .iter()
-                    .filter(|&asset| asset.name.contains(asset_name))
-                    .map(|asset| asset.to_owned())
-                    .collect::<Vec<Asset>>();
-                match filtered_assets.len() {
-                    0 => Err(AssetError::NotFound(asset_name.clone())),
-
-                    // This is safe because there is exactly 1 element
-                    1 => Ok(filtered_assets.remove(0)),
+                    .filter(|&asset| is_match(&asset.name))
+                    .filter(|&asset| !self.asset_rules.is_excluded(&asset.name))
+                    .collect::<Vec<&Asset>>();
+                let (filtered_assets, preferred) =
+                    self.asset_rules.apply_preferences(filtered_assets);
+
+                match filtered_assets.as_slice() {
+                    [] => Err(AssetError::NotFound(asset_name.to_string())),
+                    [asset] => {
+                        if let (Some(Libc::Musl), Some(Libc::Gnu)) =
+                            (platform.libc, Libc::from_asset_name(&asset.name))
+                        {
+                            return Err(AssetError::IncompatibleLibc {
+                                asset: asset.name.clone(),
+                                libc: Libc::Musl,
+                            });
+                        }
+
+                        let mut explanation = format!(
+                            "Selected '{}' matching asset name '{}'",
+                            asset.name, asset_name
+                        );
+                        if !preferred.is_empty() {
+                            explanation
+                                .push_str(&format!(" (preferred: {})", preferred.join(", ")));
+                        }
+
+                        Ok(SelectedAsset {
+                            asset: (*asset).clone(),
+                            explanation,
+                        })
+                    }
                     _ => {
-                        let assets: Vec<String> =
-                            filtered_assets.into_iter().map(|item| item.name).collect();
+                        let assets: Vec<String> = filtered_assets
+                            .into_iter()
+                            .map(|item| item.name.clone())
+                            .collect();
                         Err(AssetError::MultipleFound(assets))
                     }
                 }
             }
         }
     }
+
+    /// Everything needed to install the selected asset of the release with
+    /// the given tag. Placeholders in executable names and paths are
+    /// expanded with the same variables the asset was selected with.
+    #[allow(clippy::too_many_arguments)]
+    pub fn to_tool_asset(
+        &self,
+        tool_name: &str,
+        tag: String,
+        asset: Asset,
+        sha256: Option<BTreeMap<String, String>>,
+        client: Client,
+        platform: &Platform,
+        variables: &Variables,
+    ) -> ToolAsset {
+        ToolAsset {
+            tool_name: String::from(tool_name),
+            tag,
+            exe_names: self
+                .exe_names
+                .iter()
+                .map(|exe_name| variables.expand(exe_name))
+                .collect(),
+            install_as: self
+                .install_as
+                .as_ref()
+                .map(|install_as| variables.expand(install_as)),
+            exe_path: self
+                .exe_path
+                .as_ref()
+                .map(|exe_path| variables.expand(exe_path)),
+            auto_exe: self.auto_exe,
+            os: platform.os,
+            asset,
+            prerelease: self.tag == ToolInfoTag::Prerelease,
+            sha256,
+            client,
+        }
+    }
 }
 
 /// All information about the tool, needed to download its asset after fetching
@@ -110,20 +232,86 @@ pub struct ToolAsset {
     /// **can't** be here.
     pub tag: String,
 
-    /// Executable name inside the .tar.gz or .zip archive
-    pub exe_name: String,
+    /// Names of executables inside the .tar.gz or .zip archive. All of them
+    /// are installed
+    pub exe_names: Vec<String>,
+
+    /// Name of the first executable in the store directory. Defaults to its
+    /// name inside the archive
+    pub install_as: Option<String>,
+
+    /// Glob with the path to executables inside the archive. The `{exe}`
+    /// placeholder is replaced with the name of each executable
+    pub exe_path: Option<String>,
+
+    /// Install the only executable in the archive when the executable isn't
+    /// found by name
+    pub auto_exe: bool,
+
+    /// OS the asset is built for. Executables for Windows get the .exe
+    /// extension
+    pub os: OS,
 
     /// The selected asset
     pub asset: Asset,
 
+    /// The tool follows the prerelease channel. Recorded in the lock file so
+    /// pins aren't shared between channels.
+    pub prerelease: bool,
+
+    /// Expected SHA-256 of each executable by its name in the store
+    /// directory. Only known when the tool is installed strictly from the lock
+    /// file.
+    pub sha256: Option<BTreeMap<String, String>>,
+
     /// GitHub API client that produces the stream for downloading the asset
     pub client: Client,
 }
 
+impl ToolAsset {
+    /// File names of the executables with the .exe extension for Windows
+    pub fn exe_file_names(&self) -> Vec<String> {
+        self.exe_names
+            .iter()
+            .map(|exe_name| mk_exe_name(exe_name, self.os))
+            .collect()
+    }
+
+    /// File names of the executables in the store directory
+    pub fn installed_exe_names(&self) -> Vec<String> {
+        let mut exe_names = self.exe_file_names();
+
+        if let Some(install_as) = &self.install_as {
+            exe_names[0] = mk_exe_name(install_as, self.os);
+        }
+
+        exe_names
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::model::arch::Arch;
+    use crate::model::asset_name::AssetPattern;
+
+    #[test]
+    fn tag_from_config() {
+        assert_eq!(
+            ToolInfoTag::from_config(String::from("v13.0.0")),
+            ToolInfoTag::Specific(String::from("v13.0.0"))
+        );
+        assert_eq!(
+            ToolInfoTag::from_config(String::from("^13")),
+            ToolInfoTag::Range(String::from("^13"))
+        );
+        assert_eq!(
+            ToolInfoTag::from_config(String::from(">=0.22, <0.24")),
+            ToolInfoTag::Range(String::from(">=0.22, <0.24"))
+        );
+    }
+
     #[test]
     fn asset_found() {
         let asset_name = "asset";
@@ -131,13 +319,18 @@ mod tests {
         let tool_info = ToolInfo {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
-            exe_name: "exe".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
             tag: ToolInfoTag::Latest,
             asset_name: AssetName {
-                linux: Some(asset_name.to_string()),
-                macos: Some(asset_name.to_string()),
-                windows: Some(asset_name.to_string()),
+                linux: Some(AssetPattern::Substring(asset_name.to_string())),
+                macos: Some(AssetPattern::Substring(asset_name.to_string())),
+                windows: Some(AssetPattern::Substring(asset_name.to_string())),
+                arch_specific: BTreeMap::new(),
             },
+            asset_rules: AssetRules::default(),
         };
 
         let assets = vec![
@@ -145,25 +338,31 @@ mod tests {
                 id: 1,
                 name: "1".to_string(),
                 size: 10,
+                updated_at: None,
             },
             Asset {
                 id: 2,
                 name: asset_name.to_string(),
                 size: 50,
+                updated_at: None,
             },
             Asset {
                 id: 3,
                 name: "3".to_string(),
                 size: 77,
+                updated_at: None,
             },
         ];
 
         assert_eq!(
-            tool_info.select_asset(&assets),
+            tool_info
+                .select_asset(&assets, &Platform::current(), &Variables::default())
+                .map(|selected| selected.asset),
             Ok(Asset {
                 id: 2,
                 name: asset_name.to_string(),
-                size: 50
+                size: 50,
+                updated_at: None,
             })
         );
     }
@@ -175,13 +374,18 @@ mod tests {
         let tool_info = ToolInfo {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
-            exe_name: "exe".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
             tag: ToolInfoTag::Latest,
             asset_name: AssetName {
-                linux: Some(asset_name.to_string()),
-                macos: Some(asset_name.to_string()),
-                windows: Some(asset_name.to_string()),
+                linux: Some(AssetPattern::Substring(asset_name.to_string())),
+                macos: Some(AssetPattern::Substring(asset_name.to_string())),
+                windows: Some(AssetPattern::Substring(asset_name.to_string())),
+                arch_specific: BTreeMap::new(),
             },
+            asset_rules: AssetRules::default(),
         };
 
         let assets = vec![
@@ -189,26 +393,30 @@ mod tests {
                 id: 1,
                 name: "asset_1".to_string(),
                 size: 10,
+                updated_at: None,
             },
             Asset {
                 id: 2,
                 name: "asset_2".to_string(),
                 size: 50,
+                updated_at: None,
             },
             Asset {
                 id: 3,
                 name: "asset_3".to_string(),
                 size: 77,
+                updated_at: None,
             },
             Asset {
                 id: 3,
                 name: "not a match".to_string(),
                 size: 77,
+                updated_at: None,
             },
         ];
 
         assert_eq!(
-            tool_info.select_asset(&assets),
+            tool_info.select_asset(&assets, &Platform::current(), &Variables::default()),
             Err(AssetError::MultipleFound(vec![
                 "asset_1".into(),
                 "asset_2".into(),
@@ -224,13 +432,18 @@ mod tests {
         let tool_info = ToolInfo {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
-            exe_name: "exe".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
             tag: ToolInfoTag::Latest,
             asset_name: AssetName {
-                linux: Some(asset_name.to_string()),
-                macos: Some(asset_name.to_string()),
-                windows: Some(asset_name.to_string()),
+                linux: Some(AssetPattern::Substring(asset_name.to_string())),
+                macos: Some(AssetPattern::Substring(asset_name.to_string())),
+                windows: Some(AssetPattern::Substring(asset_name.to_string())),
+                arch_specific: BTreeMap::new(),
             },
+            asset_rules: AssetRules::default(),
         };
 
         let assets = vec![
@@ -238,42 +451,268 @@ mod tests {
                 id: 1,
                 name: "1".to_string(),
                 size: 10,
+                updated_at: None,
             },
             Asset {
                 id: 2,
                 name: "2".to_string(),
                 size: 50,
+                updated_at: None,
             },
             Asset {
                 id: 3,
                 name: "3".to_string(),
                 size: 77,
+                updated_at: None,
             },
         ];
 
         assert_eq!(
-            tool_info.select_asset(&assets),
+            tool_info.select_asset(&assets, &Platform::current(), &Variables::default()),
             Err(AssetError::NotFound(asset_name.to_string()))
         );
     }
 
+    #[test]
+    fn multiple_asset_found_resolved_by_rules() {
+        let asset_name = "asset";
+
+        let tool_info = ToolInfo {
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
+            tag: ToolInfoTag::Latest,
+            asset_name: AssetName {
+                linux: Some(AssetPattern::Substring(asset_name.to_string())),
+                macos: Some(AssetPattern::Substring(asset_name.to_string())),
+                windows: Some(AssetPattern::Substring(asset_name.to_string())),
+                arch_specific: BTreeMap::new(),
+            },
+            asset_rules: AssetRules {
+                prefer: vec!["musl".to_string()],
+                exclude: vec![".sha256".to_string()],
+            },
+        };
+
+        let assets = vec![
+            Asset {
+                id: 1,
+                name: "asset-gnu.tar.gz".to_string(),
+                size: 10,
+                updated_at: None,
+            },
+            Asset {
+                id: 2,
+                name: "asset-musl.tar.gz".to_string(),
+                size: 50,
+                updated_at: None,
+            },
+            Asset {
+                id: 3,
+                name: "asset-musl.tar.gz.sha256".to_string(),
+                size: 77,
+                updated_at: None,
+            },
+        ];
+
+        assert_eq!(
+            tool_info
+                .select_asset(&assets, &Platform::current(), &Variables::default())
+                .map(|selected| selected.asset),
+            Ok(assets[1].clone())
+        );
+    }
+
     #[test]
     fn asset_os_selector_unknown() {
         let tool_info = ToolInfo {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
-            exe_name: "exe".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
+            tag: ToolInfoTag::Latest,
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            asset_rules: AssetRules::default(),
+        };
+
+        assert_eq!(
+            tool_info.select_asset(&[], &Platform::current(), &Variables::default()),
+            Err(AssetError::OsSelectorUnknown(Platform::current()))
+        );
+    }
+
+    #[test]
+    fn asset_selected_automatically() {
+        let tool_info = ToolInfo {
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
             tag: ToolInfoTag::Latest,
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            asset_rules: AssetRules::default(),
+        };
+
+        let assets = vec![
+            Asset {
+                id: 1,
+                name: "exe-x86_64-pc-windows-msvc.zip".to_string(),
+                size: 10,
+                updated_at: None,
             },
+            Asset {
+                id: 2,
+                name: "exe-aarch64-pc-windows-msvc.zip".to_string(),
+                size: 10,
+                updated_at: None,
+            },
+        ];
+
+        let platform = Platform {
+            os: OS::Windows,
+            arch: Arch::Aarch64,
+            libc: None,
         };
 
         assert_eq!(
-            tool_info.select_asset(&[]),
-            Err(AssetError::OsSelectorUnknown)
+            tool_info
+                .select_asset(
+                    &assets,
+                    &platform,
+                    &Variables::new("exe", "v1.0.0", &platform)
+                )
+                .map(|selected| selected.asset),
+            Ok(assets[1].clone())
+        );
+    }
+
+    #[test]
+    fn libc_placeholder_expanded() {
+        let tool_info = ToolInfo {
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            exe_names: vec!["exe".to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
+            tag: ToolInfoTag::Latest,
+            asset_name: AssetName {
+                linux: Some(AssetPattern::Substring("linux-{libc}".to_string())),
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            asset_rules: AssetRules::default(),
+        };
+
+        let assets = vec![
+            Asset {
+                id: 1,
+                name: "exe-linux-gnu.tar.gz".to_string(),
+                size: 10,
+                updated_at: None,
+            },
+            Asset {
+                id: 2,
+                name: "exe-linux-musl.tar.gz".to_string(),
+                size: 10,
+                updated_at: None,
+            },
+        ];
+
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::X86_64,
+            libc: Some(Libc::Gnu),
+        };
+
+        assert_eq!(
+            tool_info
+                .select_asset(
+                    &assets,
+                    &platform,
+                    &Variables::new("exe", "v1.0.0", &platform)
+                )
+                .map(|selected| selected.asset),
+            Ok(assets[0].clone())
+        );
+    }
+
+    #[test]
+    fn tool_asset_placeholders_expanded() {
+        let tool_info = ToolInfo {
+            owner: "helix-editor".to_string(),
+            repo: "helix".to_string(),
+            exe_names: vec!["hx".to_string()],
+            install_as: Some("hx-{version}".to_string()),
+            exe_path: Some("helix-{version}-{arch}-{os}/{exe}".to_string()),
+            auto_exe: true,
+            tag: ToolInfoTag::Latest,
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            asset_rules: AssetRules::default(),
+        };
+
+        let asset = Asset {
+            id: 1,
+            name: "helix-23.03-x86_64-linux.tar.xz".to_string(),
+            size: 10,
+            updated_at: None,
+        };
+
+        let client = Client {
+            owner: "helix-editor".to_string(),
+            repo: "helix".to_string(),
+            version: "latest".to_string(),
+            proxy: None,
+        };
+
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::X86_64,
+            libc: None,
+        };
+
+        let variables = Variables::new("helix", "23.03", &platform);
+        let tool_asset = tool_info.to_tool_asset(
+            "helix",
+            "23.03".to_string(),
+            asset,
+            None,
+            client,
+            &platform,
+            &variables,
+        );
+
+        assert_eq!(tool_asset.tag, "23.03");
+        assert_eq!(tool_asset.exe_names, vec!["hx".to_string()]);
+        assert_eq!(tool_asset.install_as, Some("hx-23.03".to_string()));
+        assert_eq!(
+            tool_asset.exe_path,
+            Some("helix-23.03-x86_64-linux/{exe}".to_string())
         );
+        assert!(tool_asset.auto_exe);
+        assert_eq!(tool_asset.os, OS::Linux);
     }
 }
@@ -0,0 +1,82 @@
+//! This file contains placeholders like `{version}` or `{os}` that can be used
+//! in 'asset_name' and 'exe_name'. They are expanded after the release tag is
+//! resolved so the config doesn't need editing on every upgrade.
+
+use crate::model::platform::Platform;
+
+/// Values of all placeholders for a single tool
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Variables {
+    values: Vec<(&'static str, String)>,
+}
+
+impl Variables {
+    pub fn new(tool_name: &str, tag: &str, platform: &Platform) -> Variables {
+        let mut values = vec![
+            ("version", tag.to_owned()),
+            (
+                "version_no_v",
+                tag.strip_prefix('v').unwrap_or(tag).to_owned(),
+            ),
+            ("os", platform.os.to_string()),
+            ("arch", platform.arch.to_string()),
+            ("tool", tool_name.to_owned()),
+        ];
+
+        if let Some(libc) = platform.libc_variable() {
+            values.push(("libc", libc.to_string()));
+        }
+
+        Variables { values }
+    }
+
+    /// Replace placeholders like `{version}` with their values. Unknown
+    /// placeholders are kept as is.
+    pub fn expand(&self, template: &str) -> String {
+        self.values
+            .iter()
+            .fold(template.to_owned(), |template, (name, value)| {
+                template.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::arch::Arch;
+    use crate::model::libc::Libc;
+    use crate::model::os::OS;
+
+    #[test]
+    fn expand_all_variables() {
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::Aarch64,
+            libc: Some(Libc::Gnu),
+        };
+        let variables = Variables::new("helix", "v23.03", &platform);
+
+        assert_eq!(
+            variables.expand("{tool}-{version_no_v}-{arch}-{os}-{libc}.tar.gz"),
+            "helix-23.03-aarch64-linux-gnu.tar.gz"
+        );
+        assert_eq!(variables.expand("{tool}-{version}"), "helix-v23.03");
+    }
+
+    #[test]
+    fn unknown_variables_kept() {
+        let platform = Platform {
+            os: OS::MacOS,
+            arch: Arch::X86_64,
+            libc: None,
+        };
+        let variables = Variables::new("fd", "8.4.0", &platform);
+
+        assert_eq!(
+            variables.expand("fd-{libc}-\\d{2}-{version_no_v}"),
+            "fd-{libc}-\\d{2}-8.4.0"
+        );
+    }
+}
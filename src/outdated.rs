@@ -0,0 +1,248 @@
+use std::path::PathBuf;
+
+use crate::config::schema::{Channel, Config, ConfigAsset};
+use crate::config::toml;
+use crate::infra::client::Client;
+use crate::infra::table::fmt_table;
+use crate::model::platform::Platform;
+use crate::model::release::parse_version;
+use crate::model::repo::RepoError;
+use crate::model::tool::{Tool, ToolInfoTag};
+use crate::sync::configure::configure_tool;
+use crate::sync::lock::{self, LockedTool, Lockfile};
+use crate::sync::state::{InstalledTool, StoreState};
+
+const NONE: &str = "-";
+
+/// Compare installed versions of tools with their latest releases
+pub fn outdated(config_path: PathBuf, proxy: Option<String>) {
+    let lockfile = Lockfile::read(&lock::lock_path(&config_path));
+    toml::with_parsed_file(config_path, proxy, |config| {
+        outdated_tools(config, lockfile)
+    })
+}
+
+fn outdated_tools(config: Config, lockfile: Lockfile) {
+    let state = match config.existing_store_directory() {
+        Some(store_directory) => StoreState::read(&store_directory),
+        None => StoreState::default(),
+    };
+    let platform = Platform::current();
+
+    eprintln!(
+        "Fetching releases of {} tools (this may take a few seconds)...",
+        config.tools.len()
+    );
+
+    let rows: Vec<Vec<String>> = config
+        .tools
+        .iter()
+        .map(|(tool_name, config_asset)| {
+            outdated_row(
+                tool_name,
+                state.tools.get(tool_name),
+                resolve_latest(tool_name, config_asset).as_deref(),
+                fmt_pinned(
+                    config_asset.tag.as_deref(),
+                    lockfile.get(tool_name, &platform),
+                ),
+            )
+        })
+        .collect();
+
+    println!(
+        "{}",
+        fmt_table(&["tool", "current", "latest", "pinned"], &rows)
+    );
+}
+
+/// The newest release the tool can be updated to: the latest stable release,
+/// the newest pre-release on the prerelease channel or the newest release
+/// matching the configured range. Errors are printed and result in `None`.
+fn resolve_latest(tool_name: &str, config_asset: &ConfigAsset) -> Option<String> {
+    let mut tool_info = match configure_tool(tool_name, config_asset) {
+        Tool::Error(e) => {
+            eprintln!("{} {}", tool_name, e);
+            return None;
+        }
+        Tool::Known(tool_info) => tool_info,
+    };
+    tool_info.tag = latest_tag(tool_info.tag, config_asset.channel);
+
+    let mut client = Client {
+        owner: tool_info.owner.clone(),
+        repo: tool_info.repo.clone(),
+        version: tool_info.tag.to_str_version(),
+        proxy: config_asset.proxy.clone(),
+    };
+
+    match client.resolve_release(&tool_info.tag) {
+        Ok(release) => Some(release.tag_name),
+        Err(e) => {
+            if let Some(ureq::Error::Status(404, _)) = e.downcast_ref::<ureq::Error>() {
+                let repo_error = RepoError::NotFound {
+                    owner: tool_info.owner,
+                    repo: tool_info.repo,
+                    tag: tool_info.tag,
+                };
+                eprintln!("{} {}", tool_name, repo_error);
+            } else {
+                eprintln!("{} {}", tool_name, e);
+            }
+            None
+        }
+    }
+}
+
+/// A pinned tag is shown in the 'pinned' column, so the 'latest' column
+/// ignores it. Nightly tools keep tracking their moving tag.
+fn latest_tag(tag: ToolInfoTag, channel: Option<Channel>) -> ToolInfoTag {
+    match (tag, channel) {
+        (tag, Some(Channel::Nightly)) => tag,
+        (ToolInfoTag::Specific(_), Some(Channel::Prerelease)) => ToolInfoTag::Prerelease,
+        (ToolInfoTag::Specific(_), _) => ToolInfoTag::Latest,
+        (tag, _) => tag,
+    }
+}
+
+/// A single row of the table: tool, current, latest and pinned
+fn outdated_row(
+    tool_name: &str,
+    installed: Option<&InstalledTool>,
+    latest: Option<&str>,
+    pinned: String,
+) -> Vec<String> {
+    let current = installed.map(|installed| installed.tag.as_str());
+
+    vec![
+        tool_name.to_owned(),
+        current.unwrap_or(NONE).to_owned(),
+        fmt_latest(current, latest),
+        pinned,
+    ]
+}
+
+/// Releases newer than the installed one are marked so they stand out in long
+/// tables. Older releases (e.g. after a rollback) and tags that are not
+/// versions are never marked.
+fn fmt_latest(current: Option<&str>, latest: Option<&str>) -> String {
+    let is_newer =
+        |current: &str, latest: &str| match (parse_version(current), parse_version(latest)) {
+            (Some(current), Some(latest)) => latest > current,
+            _ => false,
+        };
+
+    match (current, latest) {
+        (_, None) => String::from("error"),
+        (Some(current), Some(latest)) if is_newer(current, latest) => format!("{} (new)", latest),
+        (_, Some(latest)) => latest.to_owned(),
+    }
+}
+
+fn fmt_pinned(tag: Option<&str>, locked_tool: Option<&LockedTool>) -> String {
+    match (tag, locked_tool) {
+        (Some(tag), _) => tag.to_owned(),
+        (None, Some(locked_tool)) => format!("{} (lock file)", locked_tool.tag),
+        (None, None) => NONE.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    fn installed_ripgrep() -> InstalledTool {
+        InstalledTool {
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            tag: String::from("13.0.0"),
+            asset_id: 42,
+            asset_name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            installed_at: 1666000000,
+            previous: vec![],
+            sha256: BTreeMap::from([(String::from("rg"), String::from("abcdef"))]),
+        }
+    }
+
+    fn locked_tool(tag: &str) -> LockedTool {
+        LockedTool {
+            tag: String::from(tag),
+            asset_id: 42,
+            asset_name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            prerelease: false,
+            sha256: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn newer_release_marked() {
+        assert_eq!(fmt_latest(Some("13.0.0"), Some("14.0.0")), "14.0.0 (new)");
+        assert_eq!(fmt_latest(Some("v0.9"), Some("v0.10.1")), "v0.10.1 (new)");
+        assert_eq!(fmt_latest(Some("13.0.0"), Some("13.0.0")), "13.0.0");
+        assert_eq!(fmt_latest(Some("14.0.0"), Some("13.0.0")), "13.0.0");
+        assert_eq!(fmt_latest(Some("v0.10.0-rc.1"), Some("v0.9.5")), "v0.9.5");
+        assert_eq!(fmt_latest(Some("nightly"), Some("nightly")), "nightly");
+        assert_eq!(fmt_latest(None, Some("14.0.0")), "14.0.0");
+        assert_eq!(fmt_latest(Some("13.0.0"), None), "error");
+    }
+
+    #[test]
+    fn pinned_tag_ignored_for_latest() {
+        let pinned = || ToolInfoTag::Specific(String::from("13.0.0"));
+
+        assert_eq!(latest_tag(pinned(), None), ToolInfoTag::Latest);
+        assert_eq!(
+            latest_tag(pinned(), Some(Channel::Stable)),
+            ToolInfoTag::Latest
+        );
+        assert_eq!(
+            latest_tag(pinned(), Some(Channel::Prerelease)),
+            ToolInfoTag::Prerelease
+        );
+        assert_eq!(
+            latest_tag(ToolInfoTag::Range(String::from("^13")), None),
+            ToolInfoTag::Range(String::from("^13"))
+        );
+        assert_eq!(
+            latest_tag(
+                ToolInfoTag::Specific(String::from("nightly")),
+                Some(Channel::Nightly)
+            ),
+            ToolInfoTag::Specific(String::from("nightly"))
+        );
+    }
+
+    #[test]
+    fn config_tag_pinned_over_lock_file() {
+        let locked = locked_tool("13.0.0");
+
+        assert_eq!(fmt_pinned(Some("^14"), Some(&locked)), "^14");
+        assert_eq!(fmt_pinned(None, Some(&locked)), "13.0.0 (lock file)");
+        assert_eq!(fmt_pinned(None, None), "-");
+    }
+
+    #[test]
+    fn outdated_rows() {
+        let installed = installed_ripgrep();
+
+        assert_eq!(
+            outdated_row(
+                "ripgrep",
+                Some(&installed),
+                Some("14.0.0"),
+                String::from("-")
+            ),
+            vec!["ripgrep", "13.0.0", "14.0.0 (new)", "-"]
+        );
+        assert_eq!(
+            outdated_row("fd", None, Some("v8.5.3"), String::from("v8.5.3")),
+            vec!["fd", "-", "v8.5.3", "v8.5.3"]
+        );
+    }
+}
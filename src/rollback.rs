@@ -0,0 +1,115 @@
+use console::{style, Emoji};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::schema::Config;
+use crate::config::toml;
+use crate::infra::err;
+use crate::infra::file::sha256_digest;
+use crate::model::platform::Platform;
+use crate::sync::install::activate_version;
+use crate::sync::lock::{self, LockedTool, Lockfile};
+use crate::sync::state::{version_dir, StoreState};
+use crate::sync::store_lock::{LockWait, StoreLock};
+
+const DONE: Emoji<'_, '_> = Emoji("✨ ", "* ");
+
+/// Switch a tool back to its previously installed version
+pub fn rollback(config_path: PathBuf, name: String, proxy: Option<String>) {
+    let lock_path = lock::lock_path(&config_path);
+    toml::with_parsed_file(config_path, proxy, |config| {
+        rollback_tool(config, name, lock_path)
+    })
+}
+
+fn rollback_tool(config: Config, name: String, lock_path: PathBuf) {
+    let store_directory = config.ensure_store_directory();
+    let _store_lock = StoreLock::acquire(&store_directory, LockWait::Wait);
+    let mut state = StoreState::read(&store_directory);
+
+    let installed = match state.tools.get_mut(&name) {
+        Some(installed) => installed,
+        None => err::abort_with(format!(
+            "The '{}' tool is not installed by 'tool-sync' in: {}",
+            name,
+            store_directory.display()
+        )),
+    };
+
+    let previous = match installed.previous.last() {
+        Some(previous) => previous.clone(),
+        None => err::abort_with(format!("No previous version of '{}' to roll back to", name)),
+    };
+
+    let previous_dir = version_dir(&name, &previous.tag);
+
+    for (exe_name, sha256) in &previous.sha256 {
+        let previous_exe = store_directory.join(&previous_dir).join(exe_name);
+
+        match sha256_digest(&previous_exe) {
+            Ok(actual) if actual == *sha256 => {}
+            _ => err::abort_with(format!(
+                "The previous version {} of '{}' is missing or was changed: {}",
+                previous.tag,
+                name,
+                previous_exe.display()
+            )),
+        }
+    }
+
+    for exe_name in previous.sha256.keys() {
+        if let Err(e) = activate_version(&store_directory, &previous_dir, exe_name) {
+            err::abort_with(format!(
+                "Error activating {} of '{}': {}",
+                previous.tag, name, e
+            ));
+        }
+    }
+
+    // Executables that only the active version has would point to the
+    // discarded version
+    for exe_name in installed.exe_names() {
+        if !previous.sha256.contains_key(exe_name) {
+            let _ = fs::remove_file(store_directory.join(exe_name));
+        }
+    }
+
+    // unwrap is safe here because the previous version exists
+    let discarded = installed.rollback().unwrap();
+    let _ = fs::remove_dir_all(store_directory.join(version_dir(&name, &discarded.tag)));
+
+    state.write(&store_directory);
+
+    // Pin the version in the lock file so the next 'tool sync' doesn't bring
+    // the discarded version back
+    let mut lockfile = Lockfile::read(&lock_path);
+    let platform = lockfile
+        .find_platform(&name, discarded.asset_id)
+        .unwrap_or_else(|| Platform::current().to_string());
+    let pinned = lockfile.tools.entry(name.clone()).or_default();
+    // The channel of the tool doesn't change with a rollback
+    let prerelease = pinned
+        .get(&platform)
+        .is_some_and(|locked_tool| locked_tool.prerelease);
+    pinned.insert(
+        platform,
+        LockedTool {
+            tag: previous.tag.clone(),
+            asset_id: previous.asset_id,
+            asset_name: previous.asset_name,
+            asset_size: previous.asset_size,
+            asset_updated_at: previous.asset_updated_at,
+            prerelease,
+            sha256: previous.sha256,
+        },
+    );
+    lockfile.write(&lock_path);
+
+    eprintln!(
+        "{}Rolled back {} from {} to {}",
+        DONE,
+        style(&name).cyan().bold(),
+        discarded.tag,
+        previous.tag
+    );
+}
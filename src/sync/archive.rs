@@ -1,14 +1,20 @@
+use bzip2::read::BzDecoder;
 use flate2::read::GzDecoder;
+use std::collections::VecDeque;
 use std::fmt::{Display, Formatter};
-use std::fs::File;
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use xz2::read::XzDecoder;
 
-use crate::model::asset_name::mk_exe_name;
+use crate::infra::file::is_executable;
 
 pub struct Archive<'a> {
     archive_path: &'a PathBuf,
     tmp_dir: &'a Path,
-    exe_name: &'a str,
+    exe_names: &'a [String],
+    exe_path: Option<&'a str>,
+    auto_exe: bool,
     archive_type: ArchiveType<'a>,
 }
 
@@ -17,12 +23,47 @@ enum ArchiveType<'a> {
     Exe(&'a str),
     Zip(&'a str),
     TarGz(&'a str),
+    TarXz(&'a str),
+    TarBz2(&'a str),
+}
+
+/// Detect the archive type by the asset extension. Archives usually contain
+/// a directory named after the asset without the extension.
+fn archive_type(asset_name: &str) -> Option<ArchiveType<'_>> {
+    let strip_extension = |extensions: &[&str]| {
+        extensions
+            .iter()
+            .find_map(|extension| asset_name.strip_suffix(extension))
+    };
+
+    if let Some(tar_gz_dir) = strip_extension(&[".tar.gz", ".tgz"]) {
+        Some(ArchiveType::TarGz(tar_gz_dir))
+    } else if let Some(tar_xz_dir) = strip_extension(&[".tar.xz", ".txz"]) {
+        Some(ArchiveType::TarXz(tar_xz_dir))
+    } else if let Some(tar_bz2_dir) = strip_extension(&[".tar.bz2", ".tbz2"]) {
+        Some(ArchiveType::TarBz2(tar_bz2_dir))
+    } else if let Some(zip_dir) = strip_extension(&[".zip"]) {
+        Some(ArchiveType::Zip(zip_dir))
+    } else if asset_name.ends_with(".exe") {
+        Some(ArchiveType::Exe(asset_name))
+    } else {
+        None
+    }
 }
 
 pub enum UnpackError {
     IOError(std::io::Error),
     ZipError(zip::result::ZipError),
-    ExeNotFound(String),
+    ExeNotFound {
+        exe_name: String,
+        archive: String,
+
+        /// Paths to all executables in the unpacked archive
+        candidates: Vec<String>,
+
+        /// Whether setting 'auto_exe' would install the only executable
+        suggest_auto_exe: bool,
+    },
 }
 
 impl Display for UnpackError {
@@ -30,8 +71,32 @@ impl Display for UnpackError {
         match self {
             UnpackError::IOError(e) => write!(f, "{}", e),
             UnpackError::ZipError(e) => write!(f, "{}", e),
-            UnpackError::ExeNotFound(archive_name) => {
-                write!(f, "Can't find executable in archive: {}", archive_name)
+            UnpackError::ExeNotFound {
+                exe_name,
+                archive,
+                candidates,
+                suggest_auto_exe,
+            } => {
+                write!(
+                    f,
+                    "Can't find executable '{}' in archive: {}",
+                    exe_name, archive
+                )?;
+
+                if let Some(suggestion) = suggest_exe_name(exe_name, candidates) {
+                    write!(
+                        f,
+                        ". Executables in the archive: {}. Perhaps you meant: exe_name = \"{}\"",
+                        candidates.join(", "),
+                        suggestion
+                    )?;
+                }
+
+                if *suggest_auto_exe {
+                    write!(f, " or set 'auto_exe = true' to install it automatically")?;
+                }
+
+                Ok(())
             }
         }
     }
@@ -41,86 +106,137 @@ impl<'a> Archive<'a> {
     pub fn from(
         archive_path: &'a PathBuf,
         tmp_dir: &'a Path,
-        exe_name: &'a str,
+        exe_names: &'a [String],
+        exe_path: Option<&'a str>,
+        auto_exe: bool,
         asset_name: &'a str,
     ) -> Option<Archive<'a>> {
-        let tar_gz_dir = asset_name.strip_suffix(".tar.gz");
-
-        match tar_gz_dir {
-            Some(tar_gz_dir) => Some(Archive {
-                archive_path,
-                tmp_dir,
-                exe_name,
-                archive_type: ArchiveType::TarGz(tar_gz_dir),
-            }),
-            None => {
-                let zip_dir = asset_name.strip_suffix(".zip");
-
-                match zip_dir {
-                    Some(zip_dir) => Some(Archive {
-                        archive_path,
-                        tmp_dir,
-                        exe_name,
-                        archive_type: ArchiveType::Zip(zip_dir),
-                    }),
-                    None => {
-                        let exe_file = asset_name.strip_suffix(".exe");
-
-                        exe_file.map(|_| Archive {
-                            archive_path,
-                            tmp_dir,
-                            exe_name,
-                            archive_type: ArchiveType::Exe(asset_name),
-                        })
-                    }
-                }
-            }
-        }
+        archive_type(asset_name).map(|archive_type| Archive {
+            archive_path,
+            tmp_dir,
+            exe_names,
+            exe_path,
+            auto_exe,
+            archive_type,
+        })
     }
 
-    /// Unpack archive and return path to the executable tool
-    pub fn unpack(&self) -> Result<PathBuf, UnpackError> {
+    /// Unpack archive and return paths to all executables in the same order
+    /// as the executable names
+    pub fn unpack(&self) -> Result<Vec<PathBuf>, UnpackError> {
         match self.archive_type {
             // already .exe file without archive (on Windows): no need to unpack
-            ArchiveType::Exe(exe_file) => Ok(PathBuf::from(exe_file)),
+            ArchiveType::Exe(exe_file) => match self.exe_names {
+                [_] => Ok(vec![PathBuf::from(exe_file)]),
+                _ => Err(UnpackError::ExeNotFound {
+                    exe_name: self.exe_names.join(", "),
+                    archive: exe_file.to_owned(),
+                    candidates: vec![],
+                    suggest_auto_exe: false,
+                }),
+            },
 
             // unpack .tar.gz archive
             ArchiveType::TarGz(asset_name) => {
-                unpack_tar(self.archive_path, self.tmp_dir).map_err(UnpackError::IOError)?;
-                find_path_to_exe(self.archive_path, self.tmp_dir, self.exe_name, asset_name)
+                File::open(self.archive_path)
+                    .and_then(|file| unpack_tar(GzDecoder::new(file), self.tmp_dir))
+                    .map_err(UnpackError::IOError)?;
+                self.find_paths_to_exes(asset_name)
+            }
+
+            // unpack .tar.xz archive
+            ArchiveType::TarXz(asset_name) => {
+                File::open(self.archive_path)
+                    .and_then(|file| unpack_tar(XzDecoder::new(file), self.tmp_dir))
+                    .map_err(UnpackError::IOError)?;
+                self.find_paths_to_exes(asset_name)
+            }
+
+            // unpack .tar.bz2 archive
+            ArchiveType::TarBz2(asset_name) => {
+                File::open(self.archive_path)
+                    .and_then(|file| unpack_tar(BzDecoder::new(file), self.tmp_dir))
+                    .map_err(UnpackError::IOError)?;
+                self.find_paths_to_exes(asset_name)
             }
 
             // unpack .zip archive
             ArchiveType::Zip(asset_name) => {
                 unpack_zip(self.archive_path, self.tmp_dir)?;
-                find_path_to_exe(self.archive_path, self.tmp_dir, self.exe_name, asset_name)
+                self.find_paths_to_exes(asset_name)
             }
         }
     }
+
+    /// A single missing executable fails the whole tool
+    fn find_paths_to_exes(&self, asset_name: &str) -> Result<Vec<PathBuf>, UnpackError> {
+        self.exe_names
+            .iter()
+            .map(|exe_name| {
+                match find_path_to_exe(self.tmp_dir, exe_name, self.exe_path, asset_name) {
+                    Some(tool_path) => Ok(tool_path),
+                    None => self.exe_not_found(exe_name),
+                }
+            })
+            .collect()
+    }
+
+    /// Executables in the archive help fixing 'exe_name' in the config. With
+    /// 'auto_exe', the only executable in the archive is installed right away.
+    fn exe_not_found(&self, exe_name: &str) -> Result<PathBuf, UnpackError> {
+        let executables: Vec<PathBuf> = unpacked_files(self.tmp_dir)
+            .into_iter()
+            .filter(|path| path != self.archive_path && is_executable(path))
+            .collect();
+
+        // 'auto_exe' only works for a single configured executable
+        let is_single_exe = executables.len() == 1 && self.exe_names.len() == 1;
+
+        match executables.as_slice() {
+            [executable] if self.auto_exe && is_single_exe => Ok(executable.clone()),
+            _ => Err(UnpackError::ExeNotFound {
+                exe_name: exe_name.to_owned(),
+                archive: format!("{}", self.archive_path.display()),
+                suggest_auto_exe: is_single_exe && !self.auto_exe,
+                candidates: executables
+                    .iter()
+                    .map(|path| {
+                        let path = path.strip_prefix(self.tmp_dir).unwrap_or(path);
+                        format!("{}", path.display())
+                    })
+                    .collect(),
+            }),
+        }
+    }
 }
 
-fn unpack_tar(tar_path: &PathBuf, tmp_dir: &Path) -> Result<(), std::io::Error> {
-    // unpack tar_path to tmp_dir
-    let tar_file = File::open(tar_path)?;
-    let tar_decoder = GzDecoder::new(tar_file);
+/// Unpack a tar archive from the decompressed stream to tmp_dir
+fn unpack_tar(tar_decoder: impl Read, tmp_dir: &Path) -> Result<(), std::io::Error> {
     let mut archive = tar::Archive::new(tar_decoder);
     archive.unpack(tmp_dir)
 }
 
 fn unpack_zip(zip_path: &PathBuf, tmp_dir: &Path) -> Result<(), UnpackError> {
-    let zip_archive_file = File::open(&zip_path).map_err(UnpackError::IOError)?;
+    let zip_archive_file = File::open(zip_path).map_err(UnpackError::IOError)?;
 
     let mut archive = zip::ZipArchive::new(zip_archive_file).map_err(UnpackError::ZipError)?;
 
     archive.extract(tmp_dir).map_err(UnpackError::ZipError)
 }
 
+/// Look for the executable in the configured path first, then in the common
+/// layouts and finally anywhere in the unpacked archive
 fn find_path_to_exe(
-    archive_path: &Path,
     tmp_dir: &Path,
     exe_name: &str,
+    exe_path: Option<&str>,
     asset_name: &str,
-) -> Result<PathBuf, UnpackError> {
+) -> Option<PathBuf> {
+    if let Some(tool_path) = exe_path.and_then(|exe_path| find_by_glob(tmp_dir, exe_path, exe_name))
+    {
+        return Some(tool_path);
+    }
+
     let path_candidates = exe_paths(exe_name, asset_name);
 
     // find a path
@@ -132,24 +248,300 @@ fn find_path_to_exe(
 
         // check if this path actually exists
         if tool_path.is_file() {
-            return Ok(tool_path);
+            return Some(tool_path);
+        }
+    }
+
+    unpacked_files(tmp_dir)
+        .into_iter()
+        .find(|path| path.file_name().is_some_and(|name| name == exe_name))
+}
+
+/// Find the executable by a glob relative to the unpacked archive, e.g.
+/// "*/bin/{exe}". The `{exe}` placeholder is replaced with the executable name.
+fn find_by_glob(tmp_dir: &Path, exe_path: &str, exe_name: &str) -> Option<PathBuf> {
+    let pattern = format!(
+        "{}/{}",
+        glob::Pattern::escape(&tmp_dir.to_string_lossy()),
+        exe_path.replace("{exe}", exe_name)
+    );
+
+    glob::glob(&pattern)
+        .ok()?
+        .filter_map(|path| path.ok())
+        .find(|path| path.is_file())
+}
+
+/// All files in the unpacked archive in the breadth-first order, so the
+/// shallowest files go first
+fn unpacked_files(tmp_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut directories = VecDeque::from([tmp_dir.to_path_buf()]);
+
+    while let Some(directory) = directories.pop_front() {
+        let mut entries: Vec<PathBuf> = match fs::read_dir(&directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect(),
+            Err(_) => continue,
+        };
+        entries.sort();
+
+        for path in entries {
+            // symlinks to directories are skipped to avoid cycles
+            if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+                directories.push_back(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+/// The executable with the closest name to the configured one, without the
+/// .exe extension like in the config
+fn suggest_exe_name(exe_name: &str, candidates: &[String]) -> Option<String> {
+    let stem = |name: &str| name.strip_suffix(".exe").unwrap_or(name).to_owned();
+    let exe_name = stem(exe_name);
+
+    candidates
+        .iter()
+        .filter_map(|candidate| Path::new(candidate).file_name())
+        .map(|file_name| stem(&file_name.to_string_lossy()))
+        .min_by_key(|candidate| edit_distance(&exe_name, candidate))
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
         }
+
+        previous = current;
     }
 
-    Err(UnpackError::ExeNotFound(format!(
-        "{}",
-        archive_path.display()
-    )))
+    previous[right.len()]
 }
 
 // List of potential paths where an executable can be inside the archive
 fn exe_paths(exe_name: &str, asset_name: &str) -> Vec<PathBuf> {
-    let exe_name = mk_exe_name(exe_name);
-
     vec![
-        [asset_name, &exe_name].iter().collect(),
-        [&exe_name].iter().collect(),
-        ["bin", &exe_name].iter().collect(),
-        [asset_name, "bin", &exe_name].iter().collect(),
+        [asset_name, exe_name].iter().collect(),
+        [exe_name].iter().collect(),
+        ["bin", exe_name].iter().collect(),
+        [asset_name, "bin", exe_name].iter().collect(),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn create_file(tmp_dir: &Path, path: &[&str]) -> PathBuf {
+        let file_path = path
+            .iter()
+            .fold(tmp_dir.to_path_buf(), |dir, p| dir.join(p));
+        fs::create_dir_all(file_path.parent().unwrap()).expect("This should not fail");
+        File::create(&file_path).expect("This should not fail");
+        file_path
+    }
+
+    fn create_executable(tmp_dir: &Path, path: &[&str]) -> PathBuf {
+        let file_path = create_file(tmp_dir, path);
+        fs::write(&file_path, b"\x7fELF\x02\x01\x01").expect("This should not fail");
+        file_path
+    }
+
+    #[test]
+    fn exe_found_by_glob() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        create_file(tmp_dir.path(), &["tool", "tool"]);
+        let exe = create_file(tmp_dir.path(), &["tool-1.0", "bin", "tool"]);
+
+        assert_eq!(
+            find_path_to_exe(tmp_dir.path(), "tool", Some("*/bin/{exe}"), "tool"),
+            Some(exe)
+        );
+    }
+
+    #[test]
+    fn exe_found_recursively() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        create_file(tmp_dir.path(), &["dist", "linux", "amd64", "deep", "tool"]);
+        let exe = create_file(tmp_dir.path(), &["dist", "linux", "amd64", "tool"]);
+
+        assert_eq!(
+            find_path_to_exe(tmp_dir.path(), "tool", None, "tool"),
+            Some(exe)
+        );
+    }
+
+    #[test]
+    fn exe_not_found_with_candidates() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        create_file(tmp_dir.path(), &["dist", "README.md"]);
+        create_executable(tmp_dir.path(), &["dist", "tool-cli"]);
+        create_executable(tmp_dir.path(), &["dist", "helper"]);
+
+        let archive_path = tmp_dir.path().join("tool.tar.gz");
+        let exe_names = [String::from("tool")];
+        let archive = Archive {
+            archive_path: &archive_path,
+            tmp_dir: tmp_dir.path(),
+            exe_names: &exe_names,
+            exe_path: None,
+            auto_exe: true,
+            archive_type: ArchiveType::TarGz("tool"),
+        };
+
+        match archive.find_paths_to_exes("tool") {
+            Err(UnpackError::ExeNotFound { candidates, .. }) => {
+                assert_eq!(
+                    candidates,
+                    vec![
+                        format!("{}", Path::new("dist").join("helper").display()),
+                        format!("{}", Path::new("dist").join("tool-cli").display()),
+                    ]
+                );
+                assert_eq!(
+                    suggest_exe_name("tool", &candidates),
+                    Some(String::from("tool-cli"))
+                );
+            }
+            _ => panic!("Expected ExeNotFound"),
+        }
+    }
+
+    #[test]
+    fn single_executable_installed_automatically() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        create_file(tmp_dir.path(), &["dist", "README.md"]);
+        let exe = create_executable(tmp_dir.path(), &["dist", "tool-cli"]);
+
+        let archive_path = tmp_dir.path().join("tool.tar.gz");
+        let exe_names = [String::from("tool")];
+        let mut archive = Archive {
+            archive_path: &archive_path,
+            tmp_dir: tmp_dir.path(),
+            exe_names: &exe_names,
+            exe_path: None,
+            auto_exe: false,
+            archive_type: ArchiveType::TarGz("tool"),
+        };
+
+        match archive.find_paths_to_exes("tool") {
+            Err(e) => assert!(e
+                .to_string()
+                .ends_with("or set 'auto_exe = true' to install it automatically")),
+            Ok(_) => panic!("Expected ExeNotFound"),
+        }
+
+        archive.auto_exe = true;
+
+        assert_eq!(archive.find_paths_to_exes("tool").ok(), Some(vec![exe]));
+    }
+
+    #[test]
+    fn no_auto_exe_hint_for_several_exe_names() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        create_executable(tmp_dir.path(), &["dist", "fzf-cli"]);
+
+        let archive_path = tmp_dir.path().join("fzf.tar.gz");
+        let exe_names = [String::from("fzf"), String::from("fzf-tmux")];
+        let archive = Archive {
+            archive_path: &archive_path,
+            tmp_dir: tmp_dir.path(),
+            exe_names: &exe_names,
+            exe_path: None,
+            auto_exe: false,
+            archive_type: ArchiveType::TarGz("fzf"),
+        };
+
+        match archive.find_paths_to_exes("fzf") {
+            Err(e) => assert!(!e.to_string().contains("auto_exe")),
+            Ok(_) => panic!("Expected ExeNotFound"),
+        }
+    }
+
+    fn tar_with_exe(exe_name: &str) -> Vec<u8> {
+        let contents = b"\x7fELF\x02\x01\x01";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        builder
+            .append_data(&mut header, format!("tool-1.0/{}", exe_name), &contents[..])
+            .expect("This should not fail");
+        builder.into_inner().expect("This should not fail")
+    }
+
+    fn unpack_archive(asset_name: &str, archive: &[u8]) -> Option<Vec<PathBuf>> {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        let archive_path = tmp_dir.path().join(asset_name);
+        fs::write(&archive_path, archive).expect("This should not fail");
+
+        let exe_names = [String::from("tool")];
+        let archive = Archive::from(
+            &archive_path,
+            tmp_dir.path(),
+            &exe_names,
+            None,
+            false,
+            asset_name,
+        )?;
+
+        archive.unpack().ok().map(|paths| {
+            paths
+                .iter()
+                .map(|path| path.strip_prefix(tmp_dir.path()).unwrap().to_path_buf())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn unpack_tar_xz() {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder
+            .write_all(&tar_with_exe("tool"))
+            .expect("This should not fail");
+        let archive = encoder.finish().expect("This should not fail");
+
+        let expected = Some(vec![Path::new("tool-1.0").join("tool")]);
+        assert_eq!(unpack_archive("tool-1.0.tar.xz", &archive), expected);
+        assert_eq!(unpack_archive("tool-1.0.txz", &archive), expected);
+    }
+
+    #[test]
+    fn unpack_tar_bz2() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::best());
+        encoder
+            .write_all(&tar_with_exe("tool"))
+            .expect("This should not fail");
+        let archive = encoder.finish().expect("This should not fail");
+
+        let expected = Some(vec![Path::new("tool-1.0").join("tool")]);
+        assert_eq!(unpack_archive("tool-1.0.tar.bz2", &archive), expected);
+        assert_eq!(unpack_archive("tool-1.0.tbz2", &archive), expected);
+    }
+
+    #[test]
+    fn unsupported_archive() {
+        assert!(archive_type("tool-1.0.tar.zst").is_none());
+        assert!(archive_type("tool-1.0.deb").is_none());
+    }
+}
@@ -1,9 +1,21 @@
-use crate::config::schema::ConfigAsset;
+use std::collections::BTreeMap;
+
+use crate::config::schema::{Channel, ConfigAsset, NIGHTLY_TAG};
 use crate::model::asset_name::AssetName;
+use crate::model::os::OS;
 use crate::model::tool::{Tool, ToolError, ToolInfo, ToolInfoTag};
 use crate::sync::db::lookup_tool;
 
+/// The 'tool' field allows configuring a known tool in a section with another
+/// name, e.g. to install several versions of the same tool
 pub fn configure_tool(tool_name: &str, config_asset: &ConfigAsset) -> Tool {
+    if let Some(name) = &config_asset.tool {
+        return match lookup_tool(name) {
+            Some(tool_info) => Tool::Known(tool_info.configure(config_asset)),
+            None => Tool::Error(ToolError::UnknownTool { name: name.clone() }),
+        };
+    }
+
     match lookup_tool(tool_name) {
         // This is a known tool; we get hardcoded info and update it with config
         Some(tool_info) => Tool::Known(tool_info.configure(config_asset)),
@@ -31,29 +43,71 @@ pub fn configure_tool(tool_name: &str, config_asset: &ConfigAsset) -> Tool {
 fn full_configure(config_asset: &ConfigAsset) -> Option<ToolInfo> {
     let owner = config_asset.owner.clone()?;
     let repo = config_asset.repo.clone()?;
-    let exe_name = config_asset
+    let exe_names = config_asset
         .exe_name
         .clone()
-        .unwrap_or_else(|| repo.clone());
-    let tag = config_asset
-        .tag
-        .clone()
-        .map(ToolInfoTag::Specific)
-        .unwrap_or(ToolInfoTag::Latest);
+        .unwrap_or_else(|| vec![repo.clone()]);
+    let tag = configure_tag(config_asset);
 
     Some(ToolInfo {
         owner,
         repo,
-        exe_name,
-        asset_name: AssetName {
-            linux: config_asset.asset_name.linux.clone(),
-            macos: config_asset.asset_name.macos.clone(),
-            windows: config_asset.asset_name.windows.clone(),
-        },
+        exe_names,
+        install_as: config_asset.install_as.clone(),
+        exe_path: config_asset.exe_path.clone(),
+        auto_exe: config_asset.auto_exe.unwrap_or(false),
+        asset_name: config_asset.asset_name.clone(),
+        asset_rules: config_asset.asset_rules.clone(),
         tag,
     })
 }
 
+/// Configure the tag from the 'tag' and 'channel' fields. An explicit tag wins
+/// over the prerelease channel. For the nightly channel, the tag names the
+/// moving tag to track.
+fn configure_tag(config_asset: &ConfigAsset) -> ToolInfoTag {
+    let tag = config_asset.tag.clone();
+
+    match config_asset.channel {
+        Some(Channel::Nightly) => {
+            ToolInfoTag::Specific(tag.unwrap_or_else(|| NIGHTLY_TAG.to_owned()))
+        }
+        Some(Channel::Prerelease) if tag.is_none() => ToolInfoTag::Prerelease,
+        _ => tag
+            .map(ToolInfoTag::from_config)
+            .unwrap_or(ToolInfoTag::Latest),
+    }
+}
+
+/// Asset names from the config replace all hardcoded asset names of the same
+/// OS. Otherwise, a hardcoded 'linux-aarch64' selector would win over
+/// 'asset_name.linux' from the config on aarch64 machines.
+fn configure_asset_name(hardcoded: &AssetName, config: &AssetName) -> AssetName {
+    let source = |os: OS| {
+        if config.has_os(os) {
+            config
+        } else {
+            hardcoded
+        }
+    };
+
+    let mut arch_specific = BTreeMap::new();
+    for os in OS::ALL {
+        for ((selector_os, arch), pattern) in &source(os).arch_specific {
+            if *selector_os == os {
+                arch_specific.insert((os, *arch), pattern.clone());
+            }
+        }
+    }
+
+    AssetName {
+        linux: source(OS::Linux).linux.clone(),
+        macos: source(OS::MacOS).macos.clone(),
+        windows: source(OS::Windows).windows.clone(),
+        arch_specific,
+    }
+}
+
 impl ToolInfo {
     /// Update hardcoded tool info with configuration from TOML
     pub fn configure(&self, config_asset: &ConfigAsset) -> ToolInfo {
@@ -66,32 +120,22 @@ impl ToolInfo {
                 .repo
                 .clone()
                 .unwrap_or_else(|| self.repo.clone()),
-            exe_name: config_asset
+            exe_names: config_asset
                 .exe_name
                 .clone()
-                .unwrap_or_else(|| self.exe_name.clone()),
-            asset_name: AssetName {
-                linux: config_asset
-                    .asset_name
-                    .linux
-                    .clone()
-                    .or_else(|| self.asset_name.linux.clone()),
-                macos: config_asset
-                    .asset_name
-                    .macos
-                    .clone()
-                    .or_else(|| self.asset_name.macos.clone()),
-                windows: config_asset
-                    .asset_name
-                    .windows
-                    .clone()
-                    .or_else(|| self.asset_name.windows.clone()),
-            },
-            tag: config_asset
-                .tag
+                .unwrap_or_else(|| self.exe_names.clone()),
+            install_as: config_asset
+                .install_as
+                .clone()
+                .or_else(|| self.install_as.clone()),
+            exe_path: config_asset
+                .exe_path
                 .clone()
-                .map(ToolInfoTag::Specific)
-                .unwrap_or(ToolInfoTag::Latest),
+                .or_else(|| self.exe_path.clone()),
+            auto_exe: config_asset.auto_exe.unwrap_or(self.auto_exe),
+            asset_name: configure_asset_name(&self.asset_name, &config_asset.asset_name),
+            asset_rules: config_asset.asset_rules.clone(),
+            tag: configure_tag(config_asset),
         }
     }
 }
@@ -100,20 +144,30 @@ impl ToolInfo {
 mod tests {
     use super::*;
 
+    use crate::model::arch::Arch;
+    use crate::model::asset_name::{AssetPattern, AssetRules};
+
     #[test]
     fn known_tool_with_empty_config_asset() {
         let tool_name = "ripgrep";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: None,
             repo: None,
             exe_name: None,
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
+                arch_specific: BTreeMap::new(),
             },
             tag: None,
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -123,20 +177,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn known_tool_in_another_section() {
+        let config_asset = ConfigAsset {
+            tool: Some(String::from("ripgrep")),
+            owner: None,
+            repo: None,
+            exe_name: None,
+            install_as: Some(String::from("rg13")),
+            exe_path: None,
+            auto_exe: None,
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            tag: Some(String::from("13.0.0")),
+            channel: None,
+            asset_rules: AssetRules::default(),
+            proxy: None,
+        };
+
+        let ripgrep = lookup_tool("ripgrep").unwrap();
+
+        assert_eq!(
+            configure_tool("ripgrep-13", &config_asset),
+            Tool::Known(ToolInfo {
+                install_as: Some(String::from("rg13")),
+                exe_path: None,
+                auto_exe: false,
+                tag: ToolInfoTag::Specific(String::from("13.0.0")),
+                ..ripgrep
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_tool_in_another_section() {
+        let config_asset = ConfigAsset {
+            tool: Some(String::from("ripgre")),
+            owner: Some(String::from("BurntSushi")),
+            repo: Some(String::from("ripgrep")),
+            exe_name: None,
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            tag: None,
+            channel: None,
+            asset_rules: AssetRules::default(),
+            proxy: None,
+        };
+
+        let tool = configure_tool("ripgrep-13", &config_asset);
+
+        assert_eq!(
+            tool,
+            Tool::Error(ToolError::UnknownTool {
+                name: String::from("ripgre")
+            })
+        );
+
+        if let Tool::Error(e) = tool {
+            assert_eq!(e.to_string(), "[error] Unknown tool 'ripgre' in 'tool'");
+        }
+    }
+
     #[test]
     fn unknown_tool_with_empty_config_asset() {
         let tool_name = "abcdef";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: None,
             repo: None,
             exe_name: None,
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
+                arch_specific: BTreeMap::new(),
             },
             tag: None,
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -151,15 +284,22 @@ mod tests {
         let tool_name = "rg";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: None,
             repo: None,
             exe_name: None,
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
+                arch_specific: BTreeMap::new(),
             },
             tag: None,
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -176,15 +316,22 @@ mod tests {
         let tool_name = "abcdef";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: Some(String::from("chshersh")),
             repo: None,
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(vec![String::from("abcdefu")]),
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
+                arch_specific: BTreeMap::new(),
             },
             tag: Some(String::from("1.2.3")),
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -199,15 +346,22 @@ mod tests {
         let tool_name = "abcdef";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("Pluto")),
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(vec![String::from("abcdefu")]),
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
-                linux: Some(String::from("my-linux")),
-                macos: Some(String::from("my-macos")),
-                windows: Some(String::from("yours-windows")),
+                linux: Some(AssetPattern::Substring(String::from("my-linux"))),
+                macos: Some(AssetPattern::Substring(String::from("my-macos"))),
+                windows: Some(AssetPattern::Substring(String::from("yours-windows"))),
+                arch_specific: BTreeMap::new(),
             },
             tag: Some(String::from("1.2.3")),
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -216,12 +370,17 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "Pluto".to_string(),
-                exe_name: "abcdefu".to_string(),
+                exe_names: vec!["abcdefu".to_string()],
+                install_as: None,
+                exe_path: None,
+                auto_exe: false,
                 asset_name: AssetName {
-                    linux: Some("my-linux".to_string()),
-                    macos: Some("my-macos".to_string()),
-                    windows: Some("yours-windows".to_string()),
+                    linux: Some(AssetPattern::Substring("my-linux".to_string())),
+                    macos: Some(AssetPattern::Substring("my-macos".to_string())),
+                    windows: Some(AssetPattern::Substring("yours-windows".to_string())),
+                    arch_specific: BTreeMap::new(),
                 },
+                asset_rules: AssetRules::default(),
                 tag: ToolInfoTag::Specific("1.2.3".to_string()),
             })
         );
@@ -232,15 +391,22 @@ mod tests {
         let tool_name = "abcdef";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("tool-sync")),
             exe_name: None,
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
-                linux: Some(String::from("my-linux")),
-                macos: Some(String::from("my-macos")),
-                windows: Some(String::from("yours-windows")),
+                linux: Some(AssetPattern::Substring(String::from("my-linux"))),
+                macos: Some(AssetPattern::Substring(String::from("my-macos"))),
+                windows: Some(AssetPattern::Substring(String::from("yours-windows"))),
+                arch_specific: BTreeMap::new(),
             },
             tag: Some(String::from("1.0.0")),
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -249,12 +415,17 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "tool-sync".to_string(),
-                exe_name: "tool-sync".to_string(),
+                exe_names: vec!["tool-sync".to_string()],
+                install_as: None,
+                exe_path: None,
+                auto_exe: false,
                 asset_name: AssetName {
-                    linux: Some("my-linux".to_string()),
-                    macos: Some("my-macos".to_string()),
-                    windows: Some("yours-windows".to_string()),
+                    linux: Some(AssetPattern::Substring("my-linux".to_string())),
+                    macos: Some(AssetPattern::Substring("my-macos".to_string())),
+                    windows: Some(AssetPattern::Substring("yours-windows".to_string())),
+                    arch_specific: BTreeMap::new(),
                 },
+                asset_rules: AssetRules::default(),
                 tag: ToolInfoTag::Specific("1.0.0".to_string()),
             })
         );
@@ -265,15 +436,22 @@ mod tests {
         let tool_name = "ripgrep";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: Some(String::from("chshersh")),
             repo: None,
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(vec![String::from("abcdefu")]),
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
+                arch_specific: BTreeMap::new(),
             },
             tag: None,
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -282,12 +460,12 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "ripgrep".to_string(),
-                exe_name: "abcdefu".to_string(),
-                asset_name: AssetName {
-                    linux: Some("unknown-linux-musl".to_string()),
-                    macos: Some("apple-darwin".to_string()),
-                    windows: Some("x86_64-pc-windows-msvc".to_string()),
-                },
+                exe_names: vec!["abcdefu".to_string()],
+                install_as: None,
+                exe_path: None,
+                auto_exe: false,
+                asset_name: lookup_tool(tool_name).unwrap().asset_name,
+                asset_rules: AssetRules::default(),
                 tag: ToolInfoTag::Latest,
             })
         );
@@ -298,15 +476,22 @@ mod tests {
         let tool_name = "ripgrep";
 
         let config_asset = ConfigAsset {
+            tool: None,
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("Pluto")),
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(vec![String::from("abcdefu")]),
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
             asset_name: AssetName {
-                linux: Some(String::from("my-linux")),
-                macos: Some(String::from("my-macos")),
-                windows: Some(String::from("yours-windows")),
+                linux: Some(AssetPattern::Substring(String::from("my-linux"))),
+                macos: Some(AssetPattern::Substring(String::from("my-macos"))),
+                windows: Some(AssetPattern::Substring(String::from("yours-windows"))),
+                arch_specific: BTreeMap::new(),
             },
             tag: Some(String::from("3.2.1")),
+            channel: None,
+            asset_rules: AssetRules::default(),
             proxy: None,
         };
 
@@ -315,14 +500,109 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "Pluto".to_string(),
-                exe_name: "abcdefu".to_string(),
+                exe_names: vec!["abcdefu".to_string()],
+                install_as: None,
+                exe_path: None,
+                auto_exe: false,
                 asset_name: AssetName {
-                    linux: Some("my-linux".to_string()),
-                    macos: Some("my-macos".to_string()),
-                    windows: Some("yours-windows".to_string()),
+                    linux: Some(AssetPattern::Substring("my-linux".to_string())),
+                    macos: Some(AssetPattern::Substring("my-macos".to_string())),
+                    windows: Some(AssetPattern::Substring("yours-windows".to_string())),
+                    arch_specific: BTreeMap::new(),
                 },
+                asset_rules: AssetRules::default(),
                 tag: ToolInfoTag::Specific("3.2.1".to_string()),
             })
         );
     }
+
+    fn channel_config_asset(tag: Option<&str>, channel: Channel) -> ConfigAsset {
+        ConfigAsset {
+            tool: None,
+            owner: Some(String::from("neovim")),
+            repo: Some(String::from("neovim")),
+            exe_name: Some(vec![String::from("nvim")]),
+            install_as: None,
+            exe_path: None,
+            auto_exe: None,
+            asset_name: AssetName {
+                linux: Some(AssetPattern::Substring(String::from("linux64"))),
+                macos: None,
+                windows: None,
+                arch_specific: BTreeMap::new(),
+            },
+            tag: tag.map(String::from),
+            channel: Some(channel),
+            asset_rules: AssetRules::default(),
+            proxy: None,
+        }
+    }
+
+    #[test]
+    fn prerelease_channel() {
+        assert_eq!(
+            configure_tag(&channel_config_asset(None, Channel::Prerelease)),
+            ToolInfoTag::Prerelease
+        );
+        assert_eq!(
+            configure_tag(&channel_config_asset(Some("v0.8.0"), Channel::Prerelease)),
+            ToolInfoTag::Specific(String::from("v0.8.0"))
+        );
+    }
+
+    #[test]
+    fn nightly_channel() {
+        assert_eq!(
+            configure_tag(&channel_config_asset(None, Channel::Nightly)),
+            ToolInfoTag::Specific(String::from("nightly"))
+        );
+        assert_eq!(
+            configure_tag(&channel_config_asset(
+                Some("latest-build"),
+                Channel::Nightly
+            )),
+            ToolInfoTag::Specific(String::from("latest-build"))
+        );
+    }
+
+    #[test]
+    fn config_asset_name_replaces_hardcoded_os() {
+        let hardcoded = AssetName {
+            linux: Some(AssetPattern::Substring(String::from("x86_64-linux"))),
+            macos: Some(AssetPattern::Substring(String::from("x86_64-darwin"))),
+            windows: None,
+            arch_specific: BTreeMap::from([
+                (
+                    (OS::Linux, Arch::Aarch64),
+                    AssetPattern::Substring(String::from("aarch64-linux")),
+                ),
+                (
+                    (OS::MacOS, Arch::Aarch64),
+                    AssetPattern::Substring(String::from("aarch64-darwin")),
+                ),
+            ]),
+        };
+
+        let config = AssetName {
+            linux: Some(AssetPattern::Substring(String::from("my-linux"))),
+            macos: None,
+            windows: None,
+            arch_specific: BTreeMap::new(),
+        };
+
+        let asset_name = configure_asset_name(&hardcoded, &config);
+
+        assert_eq!(
+            asset_name.get_name(OS::Linux, Arch::Aarch64),
+            Some(&AssetPattern::Substring(String::from("my-linux")))
+        );
+        assert_eq!(
+            asset_name.get_name(OS::Linux, Arch::X86_64),
+            Some(&AssetPattern::Substring(String::from("my-linux")))
+        );
+        assert_eq!(
+            asset_name.get_name(OS::MacOS, Arch::Aarch64),
+            Some(&AssetPattern::Substring(String::from("aarch64-darwin")))
+        );
+    }
 }
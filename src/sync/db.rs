@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
 
-use crate::model::asset_name::AssetName;
+use crate::model::arch::Arch;
+use crate::model::asset_name::{AssetName, AssetPattern, AssetRules};
+use crate::model::os::OS;
 use crate::model::tool::{ToolInfo, ToolInfoTag};
 
 const NOT_SUPPORTED: &str = "NOT_SUPPORTED";
@@ -23,6 +25,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-unknown-linux-musl",
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
+            linux_aarch64: "aarch64-unknown-linux-gnu",
+            macos_aarch64: "aarch64-apple-darwin",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -35,6 +39,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-unknown-linux-gnu",
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
+            linux_aarch64: "aarch64-unknown-linux-gnu",
+            macos_aarch64: "aarch64-apple-darwin",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -47,6 +53,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "linux-x86_64-musl",
             macos: "macos-x86_64",
             windows: NOT_SUPPORTED,
+            linux_aarch64: NOT_SUPPORTED,
+            macos_aarch64: NOT_SUPPORTED,
             tag: ToolInfoTag::Latest,
         },
     );
@@ -59,6 +67,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-unknown-linux-musl",
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
+            linux_aarch64: "aarch64-unknown-linux-gnu",
+            macos_aarch64: "aarch64-apple-darwin",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -71,6 +81,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-unknown-linux-musl",
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
+            linux_aarch64: "aarch64-unknown-linux-gnu",
+            macos_aarch64: "aarch64-apple-darwin",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -83,6 +95,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-unknown-linux-musl",
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
+            linux_aarch64: "aarch64-unknown-linux-musl",
+            macos_aarch64: "aarch64-apple-darwin",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -95,6 +109,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "Linux_amd64",
             macos: "Darwin_amd64",
             windows: "Windows_amd64",
+            linux_aarch64: "Linux_arm64",
+            macos_aarch64: "Darwin_arm64",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -107,6 +123,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-linux",
             macos: "x86_64-mac",
             windows: "x86_64-windows.zip",
+            linux_aarch64: "aarch64-linux",
+            macos_aarch64: "aarch64-mac",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -116,9 +134,11 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             owner: "BurntSushi",
             repo: "ripgrep",
             exe_name: "rg",
-            linux: "unknown-linux-musl",
-            macos: "apple-darwin",
-            windows: "x86_64-pc-windows-msvc",
+            linux: "*-x86_64-unknown-linux-musl.tar.gz",
+            macos: "*-x86_64-apple-darwin.tar.gz",
+            windows: "*-x86_64-pc-windows-msvc.zip",
+            linux_aarch64: "*-aarch64-unknown-linux-gnu.tar.gz",
+            macos_aarch64: "*-aarch64-apple-darwin.tar.gz",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -131,6 +151,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "x86_64-unknown-linux-musl.tar.gz",
             macos: "x86_64-apple-darwin.tar.gz",
             windows: "x86_64-pc-windows-msvc.zip",
+            linux_aarch64: NOT_SUPPORTED,
+            macos_aarch64: NOT_SUPPORTED,
             tag: ToolInfoTag::Latest,
         },
     );
@@ -143,6 +165,8 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             linux: "linux_amd64.tar.gz",
             macos: "macOS_amd64",
             windows: "windows_amd64.zip",
+            linux_aarch64: "linux_arm64.tar.gz",
+            macos_aarch64: "macOS_arm64",
             tag: ToolInfoTag::Latest,
         },
     );
@@ -209,6 +233,12 @@ struct StaticToolInfo {
     pub linux: &'static str,
     pub macos: &'static str,
     pub windows: &'static str,
+
+    /// Asset names for aarch64 machines. Without them, macOS uses the x86_64
+    /// asset name above (x86_64 binaries run on Apple Silicon via Rosetta)
+    /// and other OSes guess the asset from asset names
+    pub linux_aarch64: &'static str,
+    pub macos_aarch64: &'static str,
 }
 
 impl From<StaticToolInfo> for ToolInfo {
@@ -216,22 +246,219 @@ impl From<StaticToolInfo> for ToolInfo {
         ToolInfo {
             owner: static_tool_info.owner.to_string(),
             repo: static_tool_info.repo.to_string(),
-            exe_name: static_tool_info.exe_name.to_string(),
-            asset_name: AssetName {
-                linux: from_supported_asset(static_tool_info.linux),
-                macos: from_supported_asset(static_tool_info.macos),
-                windows: from_supported_asset(static_tool_info.windows),
-            },
+            exe_names: vec![static_tool_info.exe_name.to_string()],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
+            asset_name: hardcoded_asset_name(&static_tool_info),
+            asset_rules: AssetRules::default(),
             tag: static_tool_info.tag,
         }
     }
 }
 
+/// Hardcoded asset names for the whole OS are x86_64 asset names, so they are
+/// pinned to x86_64 here. Asset names from the config are not: they apply to
+/// every architecture of the OS.
+fn hardcoded_asset_name(static_tool_info: &StaticToolInfo) -> AssetName {
+    let macos_aarch64 = match static_tool_info.macos_aarch64 {
+        NOT_SUPPORTED => static_tool_info.macos,
+        asset_name => asset_name,
+    };
+
+    let arch_specific = [
+        ((OS::Linux, Arch::X86_64), static_tool_info.linux),
+        ((OS::MacOS, Arch::X86_64), static_tool_info.macos),
+        ((OS::Windows, Arch::X86_64), static_tool_info.windows),
+        ((OS::Linux, Arch::Aarch64), static_tool_info.linux_aarch64),
+        ((OS::MacOS, Arch::Aarch64), macos_aarch64),
+    ]
+    .into_iter()
+    .filter_map(|(platform, asset_name)| {
+        from_supported_asset(asset_name).map(|pattern| (platform, pattern))
+    })
+    .collect();
+
+    AssetName {
+        linux: None,
+        macos: None,
+        windows: None,
+        arch_specific,
+    }
+}
+
+/// Asset names with '*' are globs matching the full name. They keep checksum
+/// files like 'ripgrep-14.1.0-x86_64-apple-darwin.tar.gz.sha256' out.
 #[inline]
-fn from_supported_asset(asset_name: &str) -> Option<String> {
+fn from_supported_asset(asset_name: &str) -> Option<AssetPattern> {
     if asset_name == NOT_SUPPORTED {
         None
+    } else if asset_name.contains('*') {
+        Some(AssetPattern::Glob(asset_name.to_string()))
     } else {
-        Some(asset_name.to_string())
+        Some(AssetPattern::Substring(asset_name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::platform::Platform;
+    use crate::model::release::{Asset, AssetError};
+    use crate::model::variables::Variables;
+
+    #[test]
+    fn linux_aarch64_not_supported() {
+        // exa has no aarch64 assets for Linux
+        let exa = lookup_tool("exa").unwrap();
+        let platform = Platform {
+            os: OS::Linux,
+            arch: Arch::Aarch64,
+            libc: None,
+        };
+        let assets = vec![Asset {
+            id: 1,
+            name: String::from("exa-linux-x86_64-musl-v0.10.1.zip"),
+            size: 10,
+            updated_at: None,
+        }];
+
+        assert_eq!(exa.asset_name.get_name(OS::Linux, Arch::Aarch64), None);
+        assert_eq!(
+            exa.select_asset(&assets, &platform, &Variables::default())
+                .map(|selected| selected.asset),
+            Err(AssetError::OsSelectorUnknown(platform))
+        );
+    }
+
+    const PLATFORMS: [(OS, Arch); 5] = [
+        (OS::Linux, Arch::X86_64),
+        (OS::MacOS, Arch::X86_64),
+        (OS::Windows, Arch::X86_64),
+        (OS::Linux, Arch::Aarch64),
+        (OS::MacOS, Arch::Aarch64),
+    ];
+
+    fn release_asset(id: u32, name: String) -> Asset {
+        Asset {
+            id,
+            name,
+            size: 10,
+            updated_at: None,
+        }
+    }
+
+    /// Name of an asset in release '1.0.0' matched by a hardcoded selector
+    fn asset_name_for(repo: &str, pattern: &AssetPattern) -> String {
+        match pattern {
+            AssetPattern::Glob(glob) => glob.replace('*', &format!("{}-1.0.0", repo)),
+            AssetPattern::Substring(name)
+                if name.ends_with(".zip") || name.ends_with(".tar.gz") =>
+            {
+                format!("{}-1.0.0-{}", repo, name)
+            }
+            AssetPattern::Substring(name) | AssetPattern::Regex(name) => {
+                format!("{}-1.0.0-{}.tar.gz", repo, name)
+            }
+        }
+    }
+
+    #[test]
+    fn every_tool_resolved_on_every_platform() {
+        let mut unsupported = Vec::new();
+
+        for (tool_name, tool_info) in build_db() {
+            let mut expected = Vec::new();
+            let mut assets: Vec<Asset> = Vec::new();
+
+            for (os, arch) in PLATFORMS {
+                let name = tool_info
+                    .asset_name
+                    .get_name(os, arch)
+                    .map(|pattern| asset_name_for(&tool_info.repo, pattern));
+
+                if let Some(name) = &name {
+                    if !assets.iter().any(|asset| &asset.name == name) {
+                        assets.push(release_asset(assets.len() as u32, name.clone()));
+                    }
+                } else {
+                    unsupported.push((tool_name.clone(), os, arch));
+                }
+
+                expected.push((os, arch, name));
+            }
+
+            for (os, arch, name) in expected {
+                let Some(name) = name else { continue };
+                let platform = Platform {
+                    os,
+                    arch,
+                    libc: None,
+                };
+
+                let selected = tool_info
+                    .select_asset(&assets, &platform, &Variables::default())
+                    .map(|selected| selected.asset.name);
+
+                assert_eq!(selected, Ok(name), "{} on {:?} {:?}", tool_name, os, arch);
+            }
+        }
+
+        assert_eq!(
+            unsupported,
+            vec![
+                (String::from("exa"), OS::Windows, Arch::X86_64),
+                (String::from("exa"), OS::Linux, Arch::Aarch64),
+                (String::from("tool-sync"), OS::Linux, Arch::Aarch64),
+            ]
+        );
+    }
+
+    #[test]
+    fn ripgrep_skips_checksum_files() {
+        let ripgrep = lookup_tool("ripgrep").unwrap();
+        let assets: Vec<Asset> = [
+            "ripgrep-14.1.0-aarch64-apple-darwin.tar.gz",
+            "ripgrep-14.1.0-aarch64-apple-darwin.tar.gz.sha256",
+            "ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz",
+            "ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz.sha256",
+            "ripgrep-14.1.0-x86_64-apple-darwin.tar.gz",
+            "ripgrep-14.1.0-x86_64-apple-darwin.tar.gz.sha256",
+            "ripgrep-14.1.0-x86_64-pc-windows-gnu.zip",
+            "ripgrep-14.1.0-x86_64-pc-windows-gnu.zip.sha256",
+            "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+            "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip.sha256",
+            "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+            "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+            "ripgrep_14.1.0-1_amd64.deb",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, name)| release_asset(id as u32, name.to_string()))
+        .collect();
+
+        let expected = [
+            "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+            "ripgrep-14.1.0-x86_64-apple-darwin.tar.gz",
+            "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+            "ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz",
+            "ripgrep-14.1.0-aarch64-apple-darwin.tar.gz",
+        ];
+
+        for ((os, arch), name) in PLATFORMS.into_iter().zip(expected) {
+            let platform = Platform {
+                os,
+                arch,
+                libc: None,
+            };
+
+            assert_eq!(
+                ripgrep
+                    .select_asset(&assets, &platform, &Variables::default())
+                    .map(|selected| selected.asset.name),
+                Ok(name.to_string())
+            );
+        }
     }
 }
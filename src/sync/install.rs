@@ -1,6 +1,8 @@
 use indicatif::ProgressBar;
+use std::collections::BTreeMap;
 use std::error::Error;
-use std::fs;
+use std::fs::{self, File};
+use std::io;
 use std::path::{Path, PathBuf};
 use tempdir::TempDir;
 
@@ -8,12 +10,25 @@ use tempdir::TempDir;
 use std::os::unix::fs::PermissionsExt;
 
 use crate::err;
-use crate::model::asset_name::mk_exe_name;
+use crate::infra::file::sha256_digest;
 use crate::model::tool::ToolAsset;
 
 use super::archive::Archive;
 use super::download::Downloader;
 use super::progress::SyncProgress;
+use super::state::{version_dir, InstalledTool};
+
+/// Result of installing a single tool. Successful results contain SHA-256 of
+/// each executable by its name in the store directory.
+pub enum InstallStatus {
+    /// The asset was downloaded and installed
+    Installed(BTreeMap<String, String>),
+
+    /// Exactly the same asset was already installed so nothing was downloaded
+    UpToDate(BTreeMap<String, String>),
+
+    Failed,
+}
 
 pub struct Installer<'a> {
     store_directory: &'a Path,
@@ -24,7 +39,7 @@ pub struct Installer<'a> {
 impl<'a> Installer<'a> {
     /// This functions panics when it can't create a temporary directory
     /// (e.g. not enough disk space?)
-    pub fn mk(store_directory: &Path, sync_progress: SyncProgress) -> Installer {
+    pub fn mk(store_directory: &Path, sync_progress: SyncProgress) -> Installer<'_> {
         let tmp_dir = TempDir::new("tool-sync");
         match tmp_dir {
             Err(e) => {
@@ -38,22 +53,33 @@ impl<'a> Installer<'a> {
         }
     }
 
-    /// Returns `true` if the installation was successful
-    pub fn install(&self, tool_asset: ToolAsset) -> bool {
+    /// Install a tool unless the same asset is already installed
+    pub fn install(
+        &self,
+        tool_asset: &ToolAsset,
+        installed: Option<&InstalledTool>,
+    ) -> InstallStatus {
         let tool_name = &tool_asset.tool_name;
         let tag = &tool_asset.tag;
 
         let pb_msg = self.sync_progress.create_message_bar(tool_name, tag);
 
-        match self.sync_single_tool(&tool_asset, &pb_msg) {
-            Ok(_) => {
+        if let Some(sha256) =
+            installed.and_then(|i| i.up_to_date_sha256(tool_asset, self.store_directory))
+        {
+            self.sync_progress.up_to_date(pb_msg, tool_name, tag);
+            return InstallStatus::UpToDate(sha256);
+        }
+
+        match self.sync_single_tool(tool_asset, &pb_msg) {
+            Ok(sha256) => {
                 self.sync_progress.success(pb_msg, tool_name, tag);
-                true
+                InstallStatus::Installed(sha256)
             }
             Err(e) => {
                 self.sync_progress
                     .failure(pb_msg, tool_name, tag, format!("[error] {}", e));
-                false
+                InstallStatus::Failed
             }
         }
     }
@@ -62,7 +88,7 @@ impl<'a> Installer<'a> {
         &self,
         tool_asset: &ToolAsset,
         pb_msg: &ProgressBar,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
         let downloader = Downloader {
             asset: &tool_asset.asset,
             client: &tool_asset.client,
@@ -72,48 +98,241 @@ impl<'a> Installer<'a> {
 
         let download_info = downloader.download(self.tmp_dir.path())?;
 
+        let exe_names = tool_asset.exe_file_names();
         let archive = Archive::from(
             &download_info.archive_path,
             self.tmp_dir.path(),
-            &tool_asset.exe_name,
+            &exe_names,
+            tool_asset.exe_path.as_deref(),
+            tool_asset.auto_exe,
             &tool_asset.asset.name,
         );
 
         match archive {
             None => Err(format!("Unsupported asset type: {}", tool_asset.asset.name).into()),
             Some(archive) => match archive.unpack() {
-                Err(unpack_err) => {
-                    println!("{:?}", &tool_asset);
-                    Err(unpack_err.to_string().into())
-                }
-                Ok(tool_path) => {
-                    copy_file(tool_path, self.store_directory, &tool_asset.exe_name)?;
-                    Ok(())
+                Err(unpack_err) => Err(unpack_err.to_string().into()),
+                Ok(tool_paths) => {
+                    let installed_exe_names = tool_asset.installed_exe_names();
+
+                    let mut sha256 = BTreeMap::new();
+                    for (tool_path, exe_name) in tool_paths.iter().zip(&installed_exe_names) {
+                        sha256.insert(exe_name.clone(), sha256_digest(tool_path)?);
+                    }
+
+                    if let Some(expected) = &tool_asset.sha256 {
+                        check_sha256(expected, &sha256)?;
+                    }
+
+                    let version_dir = version_dir(&tool_asset.tool_name, &tool_asset.tag);
+
+                    for (tool_path, exe_name) in tool_paths.into_iter().zip(&installed_exe_names) {
+                        copy_file(
+                            tool_path,
+                            &self.store_directory.join(&version_dir),
+                            exe_name,
+                        )?;
+                    }
+                    for exe_name in &installed_exe_names {
+                        activate_version(self.store_directory, &version_dir, exe_name)?;
+                    }
+
+                    Ok(sha256)
                 }
             },
         }
     }
 }
 
-fn copy_file(tool_path: PathBuf, store_directory: &Path, exe_name: &str) -> std::io::Result<()> {
-    let exe_name = mk_exe_name(exe_name);
+/// Check SHA-256 of every unpacked executable against the lock file
+fn check_sha256(
+    expected: &BTreeMap<String, String>,
+    actual: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    for exe_name in expected.keys().chain(actual.keys()) {
+        let expected_sha256 = expected.get(exe_name).map_or("none", String::as_str);
+        let actual_sha256 = actual.get(exe_name).map_or("none", String::as_str);
+
+        if expected_sha256 != actual_sha256 {
+            return Err(format!(
+                "SHA-256 of '{}' doesn't match the lock file: expected {}, got {}",
+                exe_name, expected_sha256, actual_sha256
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the executable from the downloaded unpacked archive to the directory
+/// of the installed version
+fn copy_file(tool_path: PathBuf, version_dir: &Path, exe_name: &str) -> io::Result<()> {
+    fs::create_dir_all(version_dir)?;
+    replace_file(&tool_path, &version_dir.join(exe_name))
+}
+
+/// Point the executable in 'store_directory' to the installed version.
+/// `version_dir` is relative to 'store_directory'.
+pub fn activate_version(
+    store_directory: &Path,
+    version_dir: &Path,
+    exe_name: &str,
+) -> io::Result<()> {
+    link_exe(store_directory, &version_dir.join(exe_name), exe_name)
+}
+
+/// Replace the executable with a relative symlink. The symlink is created
+/// under a temporary name first and then renamed so the executable is never
+/// missing.
+#[cfg(target_family = "unix")]
+fn link_exe(store_directory: &Path, target: &Path, exe_name: &str) -> io::Result<()> {
+    let exe_path = store_directory.join(exe_name);
+    let tmp_link = tmp_path(&exe_path);
+
+    if tmp_link.symlink_metadata().is_ok() {
+        fs::remove_file(&tmp_link)?;
+    }
+
+    std::os::unix::fs::symlink(target, &tmp_link)?;
+    fs::rename(&tmp_link, exe_path)
+}
+
+/// Creating symlinks on Windows requires special privileges so the active
+/// version is copied instead
+#[cfg(target_family = "windows")]
+fn link_exe(store_directory: &Path, target: &Path, exe_name: &str) -> io::Result<()> {
+    replace_file(
+        &store_directory.join(target),
+        &store_directory.join(exe_name),
+    )
+}
+
+/// Replace the executable at `install_path` with a copy of `source` atomically.
+///
+/// Copying straight onto the executable fails with "Text file busy" when the
+/// executable is running and leaves a truncated file when interrupted. So the
+/// new executable is fully written to a temporary file in the same directory
+/// first and then renamed over the old one.
+fn replace_file(source: &Path, install_path: &Path) -> io::Result<()> {
+    let tmp_install_path = tmp_path(install_path);
+
+    let result = write_executable(source, &tmp_install_path)
+        .and_then(|_| fs::rename(&tmp_install_path, install_path))
+        .and_then(|_| sync_parent_directory(install_path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_install_path);
+    }
+
+    result
+}
+
+fn write_executable(source: &Path, destination: &Path) -> io::Result<()> {
+    let mut source_file = File::open(source)?;
+    let mut destination_file = File::create(destination)?;
 
-    let mut install_path = PathBuf::new();
-    install_path.push(store_directory);
-    install_path.push(exe_name);
+    io::copy(&mut source_file, &mut destination_file)?;
+    set_executable_permissions(&destination_file)?;
+    destination_file.sync_all()
+}
+
+/// Temporary file next to the given one
+fn tmp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
 
-    // Copy file from the downloaded unpacked archive to 'store_directory'
-    fs::copy(tool_path, &install_path)?;
+    path.with_file_name(format!(".{}.tool-sync-tmp", file_name))
+}
 
-    set_executable_permissions(&install_path);
+/// Make sure the rename itself survives a crash
+#[cfg(target_family = "unix")]
+fn sync_parent_directory(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => File::open(parent)?.sync_all(),
+        None => Ok(()),
+    }
+}
 
+#[cfg(target_family = "windows")]
+fn sync_parent_directory(_path: &Path) -> io::Result<()> {
     Ok(())
 }
 
 #[cfg(target_family = "windows")]
-fn set_executable_permissions(_exe_path: &PathBuf) {}
+fn set_executable_permissions(_exe_file: &File) -> io::Result<()> {
+    Ok(())
+}
 
 #[cfg(target_family = "unix")]
-fn set_executable_permissions(exe_path: &PathBuf) {
-    fs::set_permissions(exe_path, fs::Permissions::from_mode(0o755)).unwrap();
+fn set_executable_permissions(exe_file: &File) -> io::Result<()> {
+    exe_file.set_permissions(fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_existing_file() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        let source = tmp_dir.path().join("new");
+        let install_path = tmp_dir.path().join("exe");
+
+        fs::write(&source, "new executable").unwrap();
+        fs::write(&install_path, "old executable").unwrap();
+
+        replace_file(&source, &install_path).expect("This should not fail");
+
+        assert_eq!(fs::read_to_string(&install_path).unwrap(), "new executable");
+        assert!(!tmp_path(&install_path).exists());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn replaced_file_is_executable() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        let source = tmp_dir.path().join("new");
+        let install_path = tmp_dir.path().join("exe");
+
+        fs::write(&source, "new executable").unwrap();
+
+        replace_file(&source, &install_path).expect("This should not fail");
+
+        let mode = fs::metadata(&install_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn sha256_of_every_exe_checked() {
+        let expected = BTreeMap::from([
+            (String::from("fzf"), String::from("abc")),
+            (String::from("fzf-tmux"), String::from("def")),
+        ]);
+
+        assert_eq!(check_sha256(&expected, &expected.clone()), Ok(()));
+
+        let mut changed = expected.clone();
+        changed.insert(String::from("fzf-tmux"), String::from("fed"));
+        assert_eq!(
+            check_sha256(&expected, &changed),
+            Err(String::from(
+                "SHA-256 of 'fzf-tmux' doesn't match the lock file: expected def, got fed"
+            ))
+        );
+
+        let mut missing = expected.clone();
+        missing.remove("fzf-tmux");
+        assert!(check_sha256(&expected, &missing).is_err());
+        assert!(check_sha256(&missing, &expected).is_err());
+    }
+
+    #[test]
+    fn tmp_path_in_same_directory() {
+        assert_eq!(
+            tmp_path(Path::new("bin/rg")),
+            PathBuf::from("bin/.rg.tool-sync-tmp")
+        );
+    }
 }
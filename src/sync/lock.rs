@@ -0,0 +1,512 @@
+//! This file contains the lock file: the record of exact releases and assets
+//! installed by `tool sync` for each tool from the configuration file.
+
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::infra::file;
+use crate::model::platform::Platform;
+use crate::model::release::{Asset, Release};
+use crate::model::tool::{ToolAsset, ToolInfoTag};
+
+const LOCK_FILE_HEADER: &str = r#"# This file is automatically generated by tool-sync.
+# It is not intended for manual editing.
+
+"#;
+
+/// Determines how `tool sync` treats the tags pinned in the lock file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Reuse pinned tags for tools without an explicit `tag` and pin new ones
+    Pinned,
+
+    /// Ignore pinned tags and resolve releases from scratch
+    Update,
+
+    /// Fail if resolved releases or assets differ from the pinned ones
+    Locked,
+
+    /// Install pinned assets without resolving releases at all
+    Frozen,
+}
+
+impl LockMode {
+    pub fn from_flags(update: bool, locked: bool, frozen: bool) -> LockMode {
+        if frozen {
+            LockMode::Frozen
+        } else if locked {
+            LockMode::Locked
+        } else if update {
+            LockMode::Update
+        } else {
+            LockMode::Pinned
+        }
+    }
+
+    /// In the strict modes the lock file is never changed and every tool must
+    /// be installed exactly as pinned
+    pub fn is_strict(&self) -> bool {
+        matches!(self, LockMode::Locked | LockMode::Frozen)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LockError {
+    /// The tool doesn't have an entry in the lock file
+    NotLocked,
+
+    /// Release or asset resolved from GitHub differs from the pinned one
+    Mismatch {
+        locked_tag: String,
+        locked_asset_id: u32,
+        tag: String,
+        asset_id: u32,
+        asset_name: String,
+    },
+
+    /// The tag or range in the config doesn't accept the pinned release
+    TagMismatch { locked_tag: String, tag: String },
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::NotLocked => write!(
+                f,
+                "[locked] The tool is missing in the lock file. Run 'tool sync' to pin it."
+            ),
+            LockError::Mismatch {
+                locked_tag,
+                locked_asset_id,
+                tag,
+                asset_id,
+                asset_name,
+            } => write!(
+                f,
+                "[locked] Resolved {tag} (asset {asset_id}: {asset_name}) but the lock file pins {locked_tag} (asset {locked_asset_id}). Run 'tool sync' to update the lock file."
+            ),
+            LockError::TagMismatch { locked_tag, tag } => write!(
+                f,
+                "[locked] The config asks for {tag} but the lock file pins {locked_tag}. Run 'tool sync' to update the lock file."
+            ),
+        }
+    }
+}
+
+/// Contents of the lock file
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Pinned assets of each tool by platform (e.g. "linux-x86_64"). The lock
+    /// file is shared between machines and each platform needs its own asset.
+    #[serde(default)]
+    pub tools: BTreeMap<String, BTreeMap<String, LockedTool>>,
+}
+
+/// Everything tool-sync knows about the installed version of a single tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedTool {
+    /// Resolved git tag of the release (e.g. "v3.4.2")
+    pub tag: String,
+
+    /// GitHub id of the downloaded asset
+    pub asset_id: u32,
+
+    /// Full name of the downloaded asset
+    pub asset_name: String,
+
+    /// Size of the downloaded asset in bytes
+    pub asset_size: u64,
+
+    /// When the downloaded asset was last updated on GitHub. Assets of moving
+    /// tags like "nightly" change while the tag stays the same.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_updated_at: Option<String>,
+
+    /// Pinned for a tool on the prerelease channel. Such pins are ignored by
+    /// tools tracking the latest stable release and vice versa.
+    #[serde(default)]
+    pub prerelease: bool,
+
+    /// SHA-256 of each installed executable by its name in the store directory
+    pub sha256: BTreeMap<String, String>,
+}
+
+/// The lock file lives next to the configuration file and has the same name
+/// but a different extension, e.g. `$HOME/.tool.toml` is locked by
+/// `$HOME/.tool.lock`
+pub fn lock_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("lock")
+}
+
+impl Lockfile {
+    /// Read the lock file from the given path. A missing lock file is the
+    /// same as an empty one.
+    pub fn read(lock_path: &Path) -> Lockfile {
+        file::read_toml(lock_path, "lock file")
+    }
+
+    pub fn write(&self, lock_path: &Path) {
+        file::write_toml(lock_path, "lock file", LOCK_FILE_HEADER, self)
+    }
+
+    /// The asset of a tool pinned for the given platform
+    pub fn get(&self, tool_name: &str, platform: &Platform) -> Option<&LockedTool> {
+        self.tools.get(tool_name)?.get(&platform.to_string())
+    }
+
+    /// Record the installed asset of a tool for the given platform
+    pub fn pin(
+        &mut self,
+        tool_asset: &ToolAsset,
+        platform: &Platform,
+        sha256: BTreeMap<String, String>,
+    ) {
+        let locked_tool = LockedTool {
+            tag: tool_asset.tag.clone(),
+            asset_id: tool_asset.asset.id,
+            asset_name: tool_asset.asset.name.clone(),
+            asset_size: tool_asset.asset.size,
+            asset_updated_at: tool_asset.asset.updated_at.clone(),
+            prerelease: tool_asset.prerelease,
+            sha256,
+        };
+
+        self.tools
+            .entry(tool_asset.tool_name.clone())
+            .or_default()
+            .insert(platform.to_string(), locked_tool);
+    }
+
+    /// Find the platform a tool is pinned for by the pinned asset. Commands
+    /// like 'tool rollback' only know the installed asset and not the
+    /// platform it was installed for.
+    pub fn find_platform(&self, tool_name: &str, asset_id: u32) -> Option<String> {
+        self.tools
+            .get(tool_name)?
+            .iter()
+            .find(|(_, locked_tool)| locked_tool.asset_id == asset_id)
+            .map(|(platform, _)| platform.clone())
+    }
+
+    /// Forget the pinned asset of a tool for a single platform. Returns
+    /// whether the lock file changed.
+    pub fn unpin(&mut self, tool_name: &str, platform: &str) -> bool {
+        let platforms = match self.tools.get_mut(tool_name) {
+            Some(platforms) => platforms,
+            None => return false,
+        };
+
+        let is_removed = platforms.remove(platform).is_some();
+        if platforms.is_empty() {
+            self.tools.remove(tool_name);
+        }
+
+        is_removed
+    }
+}
+
+impl LockedTool {
+    /// Check that the release and asset resolved from GitHub are the pinned ones
+    pub fn check(&self, tag: &str, asset: &Asset) -> Result<(), LockError> {
+        if self.tag == tag && self.asset_id == asset.id {
+            Ok(())
+        } else {
+            Err(LockError::Mismatch {
+                locked_tag: self.tag.clone(),
+                locked_asset_id: self.asset_id,
+                tag: tag.to_owned(),
+                asset_id: asset.id,
+                asset_name: asset.name.clone(),
+            })
+        }
+    }
+
+    /// The pinned asset as if it was fetched from GitHub
+    pub fn asset(&self) -> Asset {
+        Asset {
+            id: self.asset_id,
+            name: self.asset_name.clone(),
+            size: self.asset_size,
+            updated_at: self.asset_updated_at.clone(),
+        }
+    }
+
+    /// Check that the tag or range in the config accepts the pinned release
+    pub fn check_tag(&self, tag: &ToolInfoTag) -> Result<(), LockError> {
+        if self.accepts(tag) {
+            Ok(())
+        } else {
+            Err(LockError::TagMismatch {
+                locked_tag: self.tag.clone(),
+                tag: match tag {
+                    ToolInfoTag::Latest => String::from("the latest release"),
+                    ToolInfoTag::Prerelease => String::from("the newest pre-release"),
+                    ToolInfoTag::Specific(tag) | ToolInfoTag::Range(tag) => tag.clone(),
+                },
+            })
+        }
+    }
+
+    /// The tag to resolve for a tool with the given tag in the config. Tools
+    /// that track the latest release or a version range use the pinned tag
+    /// instead. Explicit tags are kept so changing the tag in the config always
+    /// wins over the lock file. The same goes for ranges that the pinned tag
+    /// doesn't match anymore and for tools that switched the channel.
+    pub fn pin_tag(&self, tag: &ToolInfoTag) -> ToolInfoTag {
+        if self.accepts(tag) {
+            ToolInfoTag::Specific(self.tag.clone())
+        } else {
+            tag.clone()
+        }
+    }
+
+    fn accepts(&self, tag: &ToolInfoTag) -> bool {
+        match tag {
+            ToolInfoTag::Latest => !self.prerelease,
+            ToolInfoTag::Prerelease => self.prerelease,
+            ToolInfoTag::Specific(tag) => &self.tag == tag,
+            ToolInfoTag::Range(range) => self.matches_range(range),
+        }
+    }
+
+    fn matches_range(&self, range: &str) -> bool {
+        let release = Release {
+            tag_name: self.tag.clone(),
+            assets: vec![],
+            draft: false,
+            prerelease: false,
+        };
+
+        match (VersionReq::parse(range), release.version()) {
+            (Ok(version_req), Some(version)) => version_req.matches(&version),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::arch::Arch;
+    use crate::model::os::OS;
+
+    fn parse_string(contents: &str) -> Result<Lockfile, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    fn locked_ripgrep() -> LockedTool {
+        LockedTool {
+            tag: String::from("13.0.0"),
+            asset_id: 42,
+            asset_name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            prerelease: false,
+            sha256: BTreeMap::from([(String::from("rg"), String::from("abcdef"))]),
+        }
+    }
+
+    #[test]
+    fn lock_path_next_to_config() {
+        assert_eq!(
+            lock_path(Path::new("/home/user/.tool.toml")),
+            PathBuf::from("/home/user/.tool.lock")
+        );
+        assert_eq!(
+            lock_path(Path::new("tests/sync-full.toml")),
+            PathBuf::from("tests/sync-full.lock")
+        );
+    }
+
+    #[test]
+    fn empty_lock_file() {
+        assert_eq!(parse_string(LOCK_FILE_HEADER), Ok(Lockfile::default()));
+    }
+
+    #[test]
+    fn lock_file_roundtrip() {
+        let lockfile = Lockfile {
+            tools: BTreeMap::from([(
+                "ripgrep".to_owned(),
+                BTreeMap::from([("linux-x86_64".to_owned(), locked_ripgrep())]),
+            )]),
+        };
+
+        let contents = toml::to_string(&lockfile).expect("This should not fail");
+
+        assert_eq!(parse_string(&contents), Ok(lockfile));
+    }
+
+    #[test]
+    fn stable_pin_by_default() {
+        let contents = r#"
+[tools.ripgrep.linux-x86_64]
+tag = "13.0.0"
+asset_id = 42
+asset_name = "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"
+asset_size = 1000
+
+[tools.ripgrep.linux-x86_64.sha256]
+rg = "abcdef"
+"#;
+
+        let lockfile = parse_string(contents).expect("This should not fail");
+
+        assert_eq!(lockfile.tools["ripgrep"]["linux-x86_64"], locked_ripgrep());
+    }
+
+    #[test]
+    fn pinned_per_platform() {
+        let linux = Platform {
+            os: OS::Linux,
+            arch: Arch::X86_64,
+            libc: None,
+        };
+        let macos = Platform {
+            os: OS::MacOS,
+            arch: Arch::X86_64,
+            libc: None,
+        };
+
+        let mut lockfile = Lockfile {
+            tools: BTreeMap::from([(
+                "ripgrep".to_owned(),
+                BTreeMap::from([(linux.to_string(), locked_ripgrep())]),
+            )]),
+        };
+
+        assert_eq!(lockfile.get("ripgrep", &linux), Some(&locked_ripgrep()));
+        assert_eq!(lockfile.get("ripgrep", &macos), None);
+        assert_eq!(
+            lockfile.find_platform("ripgrep", 42),
+            Some(String::from("linux-x86_64"))
+        );
+
+        assert!(!lockfile.unpin("ripgrep", "macos-x86_64"));
+        assert!(lockfile.unpin("ripgrep", "linux-x86_64"));
+        assert_eq!(lockfile, Lockfile::default());
+    }
+
+    #[test]
+    fn lock_mode_from_flags() {
+        assert_eq!(LockMode::from_flags(false, false, false), LockMode::Pinned);
+        assert_eq!(LockMode::from_flags(true, false, false), LockMode::Update);
+        assert_eq!(LockMode::from_flags(false, true, false), LockMode::Locked);
+        assert_eq!(LockMode::from_flags(false, false, true), LockMode::Frozen);
+    }
+
+    #[test]
+    fn check_pinned_asset() {
+        let locked_tool = locked_ripgrep();
+
+        assert_eq!(locked_tool.check("13.0.0", &locked_tool.asset()), Ok(()));
+    }
+
+    #[test]
+    fn check_different_asset() {
+        let locked_tool = locked_ripgrep();
+        let asset = Asset {
+            id: 43,
+            name: locked_tool.asset_name.clone(),
+            size: locked_tool.asset_size,
+            updated_at: None,
+        };
+
+        assert_eq!(
+            locked_tool.check("13.0.0", &asset),
+            Err(LockError::Mismatch {
+                locked_tag: String::from("13.0.0"),
+                locked_asset_id: 42,
+                tag: String::from("13.0.0"),
+                asset_id: 43,
+                asset_name: asset.name,
+            })
+        );
+    }
+
+    #[test]
+    fn pin_latest_tag() {
+        assert_eq!(
+            locked_ripgrep().pin_tag(&ToolInfoTag::Latest),
+            ToolInfoTag::Specific(String::from("13.0.0"))
+        );
+    }
+
+    #[test]
+    fn pin_keeps_specific_tag() {
+        let tag = ToolInfoTag::Specific(String::from("12.1.1"));
+
+        assert_eq!(locked_ripgrep().pin_tag(&tag), tag);
+    }
+
+    #[test]
+    fn pin_matching_range() {
+        assert_eq!(
+            locked_ripgrep().pin_tag(&ToolInfoTag::Range(String::from("^13"))),
+            ToolInfoTag::Specific(String::from("13.0.0"))
+        );
+    }
+
+    #[test]
+    fn pin_keeps_non_matching_range() {
+        let tag = ToolInfoTag::Range(String::from("^14"));
+
+        assert_eq!(locked_ripgrep().pin_tag(&tag), tag);
+    }
+
+    #[test]
+    fn pin_same_channel_only() {
+        let stable = locked_ripgrep();
+        let prerelease = LockedTool {
+            tag: String::from("14.0.0-rc.1"),
+            prerelease: true,
+            ..locked_ripgrep()
+        };
+
+        assert_eq!(
+            stable.pin_tag(&ToolInfoTag::Prerelease),
+            ToolInfoTag::Prerelease
+        );
+        assert_eq!(
+            prerelease.pin_tag(&ToolInfoTag::Latest),
+            ToolInfoTag::Latest
+        );
+        assert_eq!(
+            prerelease.pin_tag(&ToolInfoTag::Prerelease),
+            ToolInfoTag::Specific(String::from("14.0.0-rc.1"))
+        );
+    }
+
+    #[test]
+    fn check_config_tag() {
+        let locked_tool = locked_ripgrep();
+
+        assert_eq!(locked_tool.check_tag(&ToolInfoTag::Latest), Ok(()));
+        assert_eq!(
+            locked_tool.check_tag(&ToolInfoTag::Specific(String::from("13.0.0"))),
+            Ok(())
+        );
+        assert_eq!(
+            locked_tool.check_tag(&ToolInfoTag::Range(String::from("^13"))),
+            Ok(())
+        );
+        assert_eq!(
+            locked_tool.check_tag(&ToolInfoTag::Specific(String::from("12.1.1"))),
+            Err(LockError::TagMismatch {
+                locked_tag: String::from("13.0.0"),
+                tag: String::from("12.1.1"),
+            })
+        );
+        assert_eq!(
+            locked_tool.check_tag(&ToolInfoTag::Range(String::from("^14"))),
+            Err(LockError::TagMismatch {
+                locked_tag: String::from("13.0.0"),
+                tag: String::from("^14"),
+            })
+        );
+    }
+}
@@ -1,30 +1,75 @@
 mod archive;
-mod configure;
+pub mod configure;
 pub mod db;
 mod download;
-mod install;
+pub mod install;
+pub mod lock;
+mod plan;
 mod prefetch;
 mod progress;
+pub mod state;
+pub mod store_lock;
 
 use console::Emoji;
 use std::collections::BTreeMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::config::schema::{Config, ConfigAsset};
 use crate::config::toml;
+use crate::infra::err;
+use crate::model::platform::Platform;
+use crate::model::tool::ToolAsset;
 
-use self::install::Installer;
+use self::install::{InstallStatus, Installer};
+use self::lock::{LockMode, Lockfile};
+use self::plan::print_plan;
 use self::prefetch::prefetch;
 use self::progress::SyncProgress;
 use self::progress::ToolPair;
+use self::state::{version_dir, InstalledVersion, StoreState};
+use self::store_lock::{LockWait, StoreLock};
 
-pub fn sync_from_path(config_path: PathBuf, tool: Option<String>, proxy: Option<String>) {
+/// Options of a single sync run that don't come from the configuration file
+pub struct SyncOptions {
+    /// Path to the lock file with pinned versions of tools. Tools installed
+    /// outside of the config with 'tool install' don't use the lock file.
+    pub lock_path: Option<PathBuf>,
+
+    /// How to treat versions pinned in the lock file
+    pub lock_mode: LockMode,
+
+    /// Only print what would be installed
+    pub dry_run: bool,
+
+    /// Whether to wait for other 'tool-sync' processes using the store
+    /// directory
+    pub lock_wait: LockWait,
+
+    /// Print why each asset was selected
+    pub verbose: bool,
+
+    /// Machine to install tools for
+    pub platform: Platform,
+}
+
+pub fn sync_from_path(
+    config_path: PathBuf,
+    tool: Option<String>,
+    proxy: Option<String>,
+    options: SyncOptions,
+) {
     toml::with_parsed_file(config_path.clone(), proxy, |config| {
-        sync_from_config(config, config_path, tool)
+        sync_from_config(config, config_path, tool, options)
     });
 }
 
-pub fn sync_from_config(mut config: Config, config_path: PathBuf, tool: Option<String>) {
+pub fn sync_from_config(
+    mut config: Config,
+    config_path: PathBuf,
+    tool: Option<String>,
+    options: SyncOptions,
+) {
     if config.tools.is_empty() {
         no_tools_message();
         return;
@@ -32,10 +77,10 @@ pub fn sync_from_config(mut config: Config, config_path: PathBuf, tool: Option<S
 
     match tool {
         Some(tool) => match config.tools.remove(&tool) {
-            Some(asset) => sync_single_tool(config, tool, asset),
+            Some(asset) => sync_single_tool(config, tool, asset, options),
             None => tool_not_in_config_message(&tool, &config_path),
         },
-        None => sync_from_config_no_check(config),
+        None => sync_tools(config, options, SyncScope::AllTools),
     }
 }
 
@@ -77,18 +122,51 @@ installing one of the tools natively supported by 'tool-sync'."#,
 const DONE: Emoji<'_, '_> = Emoji("✨ ", "* ");
 const DIRECTORY: Emoji<'_, '_> = Emoji("📁 ", "* ");
 
-pub fn sync_single_tool(mut config: Config, name: String, asset: ConfigAsset) {
+pub fn sync_single_tool(
+    mut config: Config,
+    name: String,
+    asset: ConfigAsset,
+    options: SyncOptions,
+) {
     config.tools = BTreeMap::from([(name, asset)]);
-    sync_from_config_no_check(config);
+    sync_tools(config, options, SyncScope::SingleTool);
+}
+
+/// Which tools of the config are synced
+enum SyncScope {
+    /// All tools. Tools removed from the config are removed from the lock
+    /// file as well.
+    AllTools,
+
+    /// A single tool. Other tools in the lock file stay untouched.
+    SingleTool,
 }
 
 /// Like `sync_from_config` but expects non-empty list of tools
-pub fn sync_from_config_no_check(config: Config) {
+fn sync_tools(config: Config, options: SyncOptions, scope: SyncScope) {
+    if options.dry_run {
+        print_dry_run(config, options);
+        return;
+    }
+
     let store_directory = config.ensure_store_directory();
+    let _store_lock = StoreLock::acquire(&store_directory, options.lock_wait);
+    let mut lockfile = read_lockfile(&options);
+
+    if let SyncScope::AllTools = scope {
+        lockfile
+            .tools
+            .retain(|tool_name, _| config.tools.contains_key(tool_name));
+    }
+
+    let tools_count = config.tools.len() as u64;
+    let tool_assets = prefetch(config.tools, &lockfile, &options);
+    let mut state = StoreState::read(&store_directory);
+    check_exe_collisions(&tool_assets, &state);
 
-    let tool_assets = prefetch(config.tools);
     if tool_assets.is_empty() {
         empty_prefetched_tool_assets_message();
+        check_strict_sync(options.lock_mode, 0, tools_count);
         return;
     }
 
@@ -104,28 +182,132 @@ pub fn sync_from_config_no_check(config: Config) {
     let installer = Installer::mk(store_directory.as_path(), sync_progress);
 
     let mut installed_tools: u64 = 0;
+    let mut up_to_date_tools: u64 = 0;
 
     for tool_asset in tool_assets {
-        let is_success = installer.install(tool_asset);
-        if is_success {
-            installed_tools += 1
+        let installed = state.tools.get(&tool_asset.tool_name);
+        let previous_sha256 = installed.map(|installed| installed.sha256.clone());
+
+        match installer.install(&tool_asset, installed) {
+            InstallStatus::Installed(sha256) => {
+                let outdated = state.record(&tool_asset, sha256.clone());
+                // Stale executables are checked through their links, so
+                // they're removed before the versions they point to
+                if let Some(previous_sha256) = &previous_sha256 {
+                    state.remove_stale_exes(&store_directory, previous_sha256);
+                }
+                remove_versions(&store_directory, &tool_asset.tool_name, &outdated);
+                lockfile.pin(&tool_asset, &options.platform, sha256);
+                installed_tools += 1
+            }
+            InstallStatus::UpToDate(sha256) => {
+                lockfile.pin(&tool_asset, &options.platform, sha256);
+                up_to_date_tools += 1
+            }
+            InstallStatus::Failed => {}
+        }
+    }
+
+    state.write(&store_directory);
+
+    // The lock file is read-only in the strict modes
+    if let Some(lock_path) = &options.lock_path {
+        if !options.lock_mode.is_strict() {
+            lockfile.write(lock_path);
         }
     }
 
-    summary_message(installed_tools, store_directory);
+    summary_message(installed_tools, up_to_date_tools, store_directory);
+    check_strict_sync(
+        options.lock_mode,
+        installed_tools + up_to_date_tools,
+        tools_count,
+    );
 }
 
-fn summary_message(installed_tools: u64, store_directory: PathBuf) {
+/// Print what `tool sync` would do. A dry run neither creates nor locks the
+/// store directory, so it works even before the first sync.
+fn print_dry_run(config: Config, options: SyncOptions) {
+    let store_directory = config.store_directory();
+    let lockfile = read_lockfile(&options);
+
+    let tools_count = config.tools.len() as u64;
+    let tool_names: Vec<String> = config.tools.keys().cloned().collect();
+    let tool_assets = prefetch(config.tools, &lockfile, &options);
+    check_exe_collisions(&tool_assets, &StoreState::read(&store_directory));
+
+    print_plan(&tool_names, &tool_assets, &store_directory);
+    check_strict_sync(options.lock_mode, tool_assets.len() as u64, tools_count);
+}
+
+fn read_lockfile(options: &SyncOptions) -> Lockfile {
+    match &options.lock_path {
+        Some(lock_path) => Lockfile::read(lock_path),
+        None => Lockfile::default(),
+    }
+}
+
+/// Exit with an error when two tools install executables with the same name.
+/// Otherwise, the last installed tool silently replaces the other one. Tools
+/// already in the store directory count too unless they're synced now.
+fn check_exe_collisions(tool_assets: &[ToolAsset], state: &StoreState) {
+    let mut exe_owners: BTreeMap<String, &str> = BTreeMap::new();
+
+    for tool_asset in tool_assets {
+        for exe_name in tool_asset.installed_exe_names() {
+            let installed_owner = state.exe_owner(&exe_name).filter(|owner| {
+                !tool_assets
+                    .iter()
+                    .any(|tool_asset| tool_asset.tool_name == *owner)
+            });
+
+            if let Some(other_tool) = exe_owners
+                .insert(exe_name.clone(), &tool_asset.tool_name)
+                .or(installed_owner)
+            {
+                err::abort_with(format!(
+                    "Both '{}' and '{}' install the '{}' executable. Use 'install_as' to install one of them under a different name",
+                    other_tool, tool_asset.tool_name, exe_name
+                ));
+            }
+        }
+    }
+}
+
+/// Remove directories of versions that are not kept anymore. Failing to remove
+/// them doesn't break anything so errors are ignored.
+fn remove_versions(store_directory: &Path, tool_name: &str, versions: &[InstalledVersion]) {
+    for version in versions {
+        let _ = fs::remove_dir_all(store_directory.join(version_dir(tool_name, &version.tag)));
+    }
+}
+
+/// Exit with an error when not all tools were installed as pinned. Otherwise
+/// CI happily continues with missing or different tools.
+fn check_strict_sync(lock_mode: LockMode, synced_tools: u64, tools_count: u64) {
+    if lock_mode.is_strict() && synced_tools < tools_count {
+        err::abort_with(format!(
+            "Installed only {} of {} tools from the lock file",
+            synced_tools, tools_count
+        ));
+    }
+}
+
+fn summary_message(installed_tools: u64, up_to_date_tools: u64, store_directory: PathBuf) {
     eprintln!(
         "{} Successfully installed {} {}!",
         DONE,
         installed_tools,
-        if installed_tools == 1 {
-            "tool"
-        } else {
-            "tools"
-        }
+        pluralize_tools(installed_tools)
     );
+    if up_to_date_tools > 0 {
+        eprintln!(
+            "{} Already up to date: {} {}",
+            DONE,
+            up_to_date_tools,
+            pluralize_tools(up_to_date_tools)
+        );
+    }
     eprintln!(
         "{} Installation directory: {}",
         DIRECTORY,
@@ -133,6 +315,14 @@ fn summary_message(installed_tools: u64, store_directory: PathBuf) {
     );
 }
 
+fn pluralize_tools(count: u64) -> &'static str {
+    if count == 1 {
+        "tool"
+    } else {
+        "tools"
+    }
+}
+
 fn empty_prefetched_tool_assets_message() {
     eprintln!(r"Nothing to sync or encountered multiple errors prefetching tools.")
 }
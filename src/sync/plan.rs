@@ -0,0 +1,194 @@
+//! This file contains the execution plan of `tool sync --dry-run`: what would
+//! happen to each tool without downloading or installing anything.
+
+use indicatif::HumanBytes;
+use std::path::Path;
+
+use crate::infra::table::fmt_table;
+use crate::model::tool::ToolAsset;
+
+use super::state::{InstalledTool, StoreState};
+
+/// What `tool sync` would do with a single tool
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlanAction {
+    /// The tool is not installed yet
+    Install,
+
+    /// A different release is installed
+    Upgrade { from: String },
+
+    /// The same release is installed but with a different asset or the
+    /// executable was changed since the installation
+    Reinstall,
+
+    /// Exactly the same asset is already installed
+    UpToDate,
+}
+
+impl PlanAction {
+    pub fn new(
+        tool_asset: &ToolAsset,
+        installed: Option<&InstalledTool>,
+        store_directory: &Path,
+    ) -> PlanAction {
+        match installed {
+            None => PlanAction::Install,
+            Some(installed) => {
+                if installed
+                    .up_to_date_sha256(tool_asset, store_directory)
+                    .is_some()
+                {
+                    PlanAction::UpToDate
+                } else if installed.tag != tool_asset.tag {
+                    PlanAction::Upgrade {
+                        from: installed.tag.clone(),
+                    }
+                } else {
+                    PlanAction::Reinstall
+                }
+            }
+        }
+    }
+
+    fn fmt(&self, tag: &str) -> String {
+        match self {
+            PlanAction::Install => format!("install {}", tag),
+            PlanAction::Upgrade { from } => format!("upgrade {} -> {}", from, tag),
+            PlanAction::Reinstall => format!("reinstall {}", tag),
+            PlanAction::UpToDate => format!("up to date {}", tag),
+        }
+    }
+}
+
+/// Print what would happen to each tool. Tools that were configured but
+/// couldn't be prefetched are reported as errors; the reason is already printed
+/// by prefetch.
+pub fn print_plan(tool_names: &[String], tool_assets: &[ToolAsset], store_directory: &Path) {
+    let state = StoreState::read(store_directory);
+
+    let rows: Vec<Vec<String>> = tool_names
+        .iter()
+        .map(
+            |tool_name| match tool_assets.iter().find(|ta| ta.tool_name == *tool_name) {
+                None => vec![
+                    tool_name.clone(),
+                    String::from("error"),
+                    String::from("-"),
+                    String::from("-"),
+                ],
+                Some(tool_asset) => {
+                    let action =
+                        PlanAction::new(tool_asset, state.tools.get(tool_name), store_directory);
+                    let size = match action {
+                        PlanAction::UpToDate => String::from("-"),
+                        _ => HumanBytes(tool_asset.asset.size).to_string(),
+                    };
+
+                    vec![
+                        tool_name.clone(),
+                        action.fmt(&tool_asset.tag),
+                        tool_asset.asset.name.clone(),
+                        size,
+                    ]
+                }
+            },
+        )
+        .collect();
+
+    println!(
+        "{}",
+        fmt_table(&["tool", "action", "asset", "download"], &rows)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use crate::infra::client::Client;
+    use crate::model::asset_name::mk_exe_name;
+    use crate::model::os::OS;
+    use crate::model::release::Asset;
+
+    fn ripgrep_asset(tag: &str, asset_id: u32) -> ToolAsset {
+        ToolAsset {
+            tool_name: String::from("ripgrep"),
+            tag: String::from(tag),
+            exe_names: vec![String::from("rg")],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
+            os: OS::Linux,
+            asset: Asset {
+                id: asset_id,
+                name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+                size: 1000,
+                updated_at: None,
+            },
+            prerelease: false,
+            sha256: None,
+            client: Client {
+                owner: String::from("BurntSushi"),
+                repo: String::from("ripgrep"),
+                version: String::from("latest"),
+                proxy: None,
+            },
+        }
+    }
+
+    fn installed_ripgrep() -> InstalledTool {
+        InstalledTool {
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            tag: String::from("13.0.0"),
+            asset_id: 42,
+            asset_name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            installed_at: 1666000000,
+            previous: vec![],
+            sha256: BTreeMap::from([(mk_exe_name("rg", OS::Linux), String::from("abcdef"))]),
+        }
+    }
+
+    #[test]
+    fn plan_install() {
+        assert_eq!(
+            PlanAction::new(&ripgrep_asset("13.0.0", 42), None, Path::new("bin")),
+            PlanAction::Install
+        );
+    }
+
+    #[test]
+    fn plan_upgrade() {
+        let installed = installed_ripgrep();
+
+        assert_eq!(
+            PlanAction::new(
+                &ripgrep_asset("14.0.0", 43),
+                Some(&installed),
+                Path::new("bin")
+            ),
+            PlanAction::Upgrade {
+                from: String::from("13.0.0")
+            }
+        );
+    }
+
+    #[test]
+    fn plan_reinstall_missing_executable() {
+        let installed = installed_ripgrep();
+
+        assert_eq!(
+            PlanAction::new(
+                &ripgrep_asset("13.0.0", 42),
+                Some(&installed),
+                Path::new("non-existing-directory")
+            ),
+            PlanAction::Reinstall
+        );
+    }
+}
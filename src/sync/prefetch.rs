@@ -5,6 +5,8 @@ use std::collections::BTreeMap;
 use std::fmt::Display;
 
 use super::configure::configure_tool;
+use super::lock::{LockError, LockMode, LockedTool, Lockfile};
+use super::SyncOptions;
 use crate::config::schema::ConfigAsset;
 use crate::infra::client::Client;
 use crate::model::release::AssetError;
@@ -13,6 +15,7 @@ use crate::model::tool::{Tool, ToolAsset};
 
 const PREFETCH: Emoji<'_, '_> = Emoji("🔄 ", "-> ");
 const ERROR: Emoji<'_, '_> = Emoji("❌ ", "x ");
+const INFO: Emoji<'_, '_> = Emoji("💡 ", "* ");
 const PACKAGE: Emoji<'_, '_> = Emoji("📦 ", "# ");
 
 struct PrefetchProgress {
@@ -40,6 +43,11 @@ impl PrefetchProgress {
         }
     }
 
+    fn info_msg(&self, tool_name: &str, msg: &str) {
+        let tool = format!("{}", style(tool_name).cyan().bold());
+        self.pb.println(format!("{} {} {}", INFO, tool, msg))
+    }
+
     /// This method can take in any type that implements the [`Display`] trait
     fn expected_err_msg<Message: Display>(&self, tool_name: &str, msg: Message) {
         let tool = format!("{}", style(tool_name).cyan().bold());
@@ -74,7 +82,15 @@ don't hesitate to open an issue:
 ///
 ///   1. Resolving all the required fields from `ConfigAsset`.
 ///   2. Fetching release and asset info from GitHub.
-pub fn prefetch(tools: BTreeMap<String, ConfigAsset>) -> Vec<ToolAsset> {
+///
+/// Depending on the lock mode, tags and assets pinned in `lockfile` are either
+/// reused, ignored, verified or installed as is. Assets are selected for the
+/// target platform and, with `verbose`, the reason for each choice is printed.
+pub fn prefetch(
+    tools: BTreeMap<String, ConfigAsset>,
+    lockfile: &Lockfile,
+    options: &SyncOptions,
+) -> Vec<ToolAsset> {
     let total_count = tools.len();
 
     let prefetch_progress = PrefetchProgress::new(total_count);
@@ -88,9 +104,10 @@ pub fn prefetch(tools: BTreeMap<String, ConfigAsset>) -> Vec<ToolAsset> {
             prefetch_tool(
                 tool_name,
                 config_asset,
+                lockfile.get(tool_name, &options.platform),
+                options,
                 &prefetch_progress,
                 index,
-                config_asset.proxy.clone(),
             )
         })
         .collect();
@@ -111,12 +128,14 @@ pub fn prefetch(tools: BTreeMap<String, ConfigAsset>) -> Vec<ToolAsset> {
 fn prefetch_tool(
     tool_name: &str,
     config_asset: &ConfigAsset,
+    pinned: Option<&LockedTool>,
+    options: &SyncOptions,
     prefetch_progress: &PrefetchProgress,
     current_index: usize,
-    proxy: Option<ureq::Proxy>,
 ) -> Option<ToolAsset> {
     // indexes start with 0 so we add 1 to calculate already fetched tools
     let already_completed = current_index + 1;
+    let lock_mode = options.lock_mode;
 
     match configure_tool(tool_name, config_asset) {
         Tool::Error(e) => {
@@ -125,14 +144,60 @@ fn prefetch_tool(
             None
         }
         Tool::Known(tool_info) => {
-            let client = Client {
+            let pinned = match lock_mode {
+                LockMode::Update => None,
+                _ => pinned,
+            };
+
+            if lock_mode.is_strict() && pinned.is_none() {
+                prefetch_progress.expected_err_msg(tool_name, LockError::NotLocked);
+                prefetch_progress.update_message(already_completed);
+                return None;
+            }
+
+            let tag = match pinned {
+                None => tool_info.tag.clone(),
+                Some(locked_tool) => {
+                    // Frozen tools never ask GitHub, so the lock file must
+                    // still match the config
+                    if lock_mode == LockMode::Frozen {
+                        if let Err(e) = locked_tool.check_tag(&tool_info.tag) {
+                            prefetch_progress.expected_err_msg(tool_name, e);
+                            prefetch_progress.update_message(already_completed);
+                            return None;
+                        }
+                    }
+
+                    locked_tool.pin_tag(&tool_info.tag)
+                }
+            };
+
+            let mut client = Client {
                 owner: tool_info.owner.clone(),
                 repo: tool_info.repo.clone(),
-                version: tool_info.tag.to_str_version(),
-                proxy,
+                version: tag.to_str_version(),
+                proxy: config_asset.proxy.clone(),
             };
 
-            match client.fetch_release_info() {
+            // Frozen tools are installed from the lock file without asking GitHub
+            if let (LockMode::Frozen, Some(locked_tool)) = (lock_mode, pinned) {
+                prefetch_progress.update_message(already_completed);
+
+                let variables =
+                    config_asset.variables(tool_name, &locked_tool.tag, &options.platform);
+
+                return Some(tool_info.to_tool_asset(
+                    tool_name,
+                    locked_tool.tag.clone(),
+                    locked_tool.asset(),
+                    Some(locked_tool.sha256.clone()),
+                    client,
+                    &options.platform,
+                    &variables,
+                ));
+            }
+
+            match client.resolve_release(&tag) {
                 Err(e) => {
                     if let Some(ureq::Error::Status(404, _)) = e.downcast_ref::<ureq::Error>() {
                         prefetch_progress.unexpected_err_msg(
@@ -140,7 +205,7 @@ fn prefetch_tool(
                             RepoError::NotFound {
                                 owner: tool_info.owner,
                                 repo: tool_info.repo,
-                                tag: tool_info.tag,
+                                tag,
                             },
                         );
                     } else {
@@ -150,33 +215,58 @@ fn prefetch_tool(
                     prefetch_progress.update_message(already_completed);
                     None
                 }
-                Ok(release) => match tool_info.select_asset(&release.assets) {
-                    Err(err) => match err {
-                        AssetError::MultipleFound(_) => {
-                            prefetch_progress.expected_err_msg(tool_name, err);
-                            prefetch_progress.update_message(already_completed);
-                            None
-                        }
-                        _ => {
-                            prefetch_progress.unexpected_err_msg(tool_name, err);
+                Ok(release) => {
+                    let variables =
+                        config_asset.variables(tool_name, &release.tag_name, &options.platform);
+
+                    match tool_info.select_asset(&release.assets, &options.platform, &variables) {
+                        Err(err) => match err {
+                            AssetError::MultipleFound(_) => {
+                                prefetch_progress.expected_err_msg(tool_name, err);
+                                prefetch_progress.update_message(already_completed);
+                                None
+                            }
+                            _ => {
+                                prefetch_progress.unexpected_err_msg(tool_name, err);
+                                prefetch_progress.update_message(already_completed);
+                                None
+                            }
+                        },
+                        Ok(selected) => {
+                            if options.verbose {
+                                prefetch_progress.info_msg(tool_name, &selected.explanation);
+                            }
+
+                            let asset = selected.asset;
+                            let sha256 = match (lock_mode, pinned) {
+                                (LockMode::Locked, Some(locked_tool)) => {
+                                    if let Err(e) = locked_tool.check(&release.tag_name, &asset) {
+                                        prefetch_progress.expected_err_msg(tool_name, e);
+                                        prefetch_progress.update_message(already_completed);
+                                        return None;
+                                    }
+
+                                    Some(locked_tool.sha256.clone())
+                                }
+                                _ => None,
+                            };
+
+                            let tool_asset = tool_info.to_tool_asset(
+                                tool_name,
+                                release.tag_name,
+                                asset,
+                                sha256,
+                                client,
+                                &options.platform,
+                                &variables,
+                            );
+
                             prefetch_progress.update_message(already_completed);
-                            None
+
+                            Some(tool_asset)
                         }
-                    },
-                    Ok(asset) => {
-                        let tool_asset = ToolAsset {
-                            tool_name: String::from(tool_name),
-                            tag: release.tag_name,
-                            exe_name: tool_info.exe_name,
-                            asset,
-                            client,
-                        };
-
-                        prefetch_progress.update_message(already_completed);
-
-                        Some(tool_asset)
                     }
-                },
+                }
             }
         }
     }
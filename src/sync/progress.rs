@@ -90,6 +90,14 @@ impl SyncProgress {
         pb.finish();
     }
 
+    pub fn up_to_date(&self, pb: ProgressBar, tool_name: &str, tag: &str) {
+        pb.set_prefix(self.fmt_prefix(SUCCESS, tool_name, tag));
+
+        let up_to_date_msg = format!("{}", style("Up to date").bold().dim());
+        pb.set_message(up_to_date_msg);
+        pb.finish();
+    }
+
     /// This method can take in any type that implements the [`Display`] trait
     pub fn failure<Message: Display>(
         &self,
@@ -0,0 +1,567 @@
+//! This file contains the state of the store directory: the list of tools
+//! installed there by 'tool-sync'.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::infra::file::{self, sha256_digest};
+use crate::model::tool::ToolAsset;
+
+/// Directory inside 'store_directory' with all the files 'tool-sync' needs
+/// for managing the installed tools
+pub const STATE_DIRECTORY: &str = ".tool-sync";
+
+const STATE_FILE: &str = "state.toml";
+
+/// How many previously installed versions of each tool are kept for rollbacks
+const MAX_PREVIOUS_VERSIONS: usize = 2;
+
+const STATE_FILE_HEADER: &str = r#"# This file is automatically generated by tool-sync.
+# It lists all tools installed in this directory. Don't edit it manually.
+
+"#;
+
+/// All tools installed in the store directory
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreState {
+    #[serde(default)]
+    pub tools: BTreeMap<String, InstalledTool>,
+}
+
+/// A single tool installed in the store directory. Each installed version
+/// lives in its own directory and the executable in the store directory points
+/// to the active one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledTool {
+    /// GitHub repository author
+    #[serde(default)]
+    pub owner: String,
+
+    /// GitHub repository name
+    #[serde(default)]
+    pub repo: String,
+
+    /// Git tag of the active release
+    pub tag: String,
+
+    /// GitHub id of the active asset
+    pub asset_id: u32,
+
+    /// Full name of the active asset
+    pub asset_name: String,
+
+    /// Size of the active asset in bytes
+    #[serde(default)]
+    pub asset_size: u64,
+
+    /// When the active asset was last updated on GitHub
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_updated_at: Option<String>,
+
+    /// Installation time in seconds since the Unix epoch
+    #[serde(default)]
+    pub installed_at: u64,
+
+    /// Previously active versions available for rollbacks, the most recent one
+    /// is the last
+    #[serde(default)]
+    pub previous: Vec<InstalledVersion>,
+
+    /// SHA-256 of each active executable by its name in the store directory
+    pub sha256: BTreeMap<String, String>,
+}
+
+/// A previously installed version of a tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledVersion {
+    pub tag: String,
+    pub asset_id: u32,
+    pub asset_name: String,
+    pub asset_size: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_updated_at: Option<String>,
+
+    pub installed_at: u64,
+    pub sha256: BTreeMap<String, String>,
+}
+
+pub fn state_path(store_directory: &Path) -> PathBuf {
+    store_directory.join(STATE_DIRECTORY).join(STATE_FILE)
+}
+
+/// Directory with all installed versions of a tool, relative to the store
+/// directory
+pub fn tool_dir(tool_name: &str) -> PathBuf {
+    [STATE_DIRECTORY, tool_name].iter().collect()
+}
+
+/// Directory with a single installed version of a tool, relative to the store
+/// directory. Tags like "release/1.0" are flattened so each version gets
+/// exactly one directory.
+pub fn version_dir(tool_name: &str, tag: &str) -> PathBuf {
+    tool_dir(tool_name).join(tag.replace(['/', '\\'], "_"))
+}
+
+impl StoreState {
+    pub fn read(store_directory: &Path) -> StoreState {
+        file::read_toml(&state_path(store_directory), "store state")
+    }
+
+    pub fn write(&self, store_directory: &Path) {
+        file::write_toml(
+            &state_path(store_directory),
+            "store state",
+            STATE_FILE_HEADER,
+            self,
+        )
+    }
+
+    /// Record the freshly installed asset of a tool. The previously active
+    /// version is kept for rollbacks. Returns versions that are too old to be
+    /// kept anymore.
+    pub fn record(
+        &mut self,
+        tool_asset: &ToolAsset,
+        sha256: BTreeMap<String, String>,
+    ) -> Vec<InstalledVersion> {
+        let mut previous = match self.tools.remove(&tool_asset.tool_name) {
+            None => Vec::new(),
+            Some(installed) => {
+                let mut previous = installed.previous.clone();
+                previous.push(installed.active_version());
+                previous
+            }
+        };
+
+        // The same tag is installed into the same directory
+        previous.retain(|version| version.tag != tool_asset.tag);
+
+        let outdated_count = previous.len().saturating_sub(MAX_PREVIOUS_VERSIONS);
+        let outdated: Vec<InstalledVersion> = previous.drain(..outdated_count).collect();
+
+        self.tools.insert(
+            tool_asset.tool_name.clone(),
+            InstalledTool {
+                owner: tool_asset.client.owner.clone(),
+                repo: tool_asset.client.repo.clone(),
+                tag: tool_asset.tag.clone(),
+                asset_id: tool_asset.asset.id,
+                asset_name: tool_asset.asset.name.clone(),
+                asset_size: tool_asset.asset.size,
+                asset_updated_at: tool_asset.asset.updated_at.clone(),
+                installed_at: now_unix_secs(),
+                previous,
+                sha256,
+            },
+        );
+
+        outdated
+    }
+
+    /// Remove executables of a previously active version that no installed
+    /// tool owns anymore, e.g. after changing `install_as`. Changed
+    /// executables are kept. Failing to remove them doesn't break anything so
+    /// errors are ignored.
+    pub fn remove_stale_exes(
+        &self,
+        store_directory: &Path,
+        previous_sha256: &BTreeMap<String, String>,
+    ) {
+        for (exe_name, sha256) in previous_sha256 {
+            let exe_path = store_directory.join(exe_name);
+
+            if self.exe_owner(exe_name).is_none()
+                && check_unchanged_exe(store_directory, exe_name, sha256).is_ok()
+                && exe_path.symlink_metadata().is_ok()
+            {
+                let _ = fs::remove_file(exe_path);
+            }
+        }
+    }
+
+    /// Name of the tool that installed the executable into the store directory
+    pub fn exe_owner(&self, exe_name: &str) -> Option<&str> {
+        self.tools
+            .iter()
+            .find(|(_, installed)| installed.sha256.contains_key(exe_name))
+            .map(|(tool_name, _)| tool_name.as_str())
+    }
+}
+
+impl InstalledTool {
+    /// Check whether the given asset is the installed one. Assets with a known
+    /// SHA-256 must also match all installed executables. Assets of moving
+    /// tags like "nightly" must also have the same update time.
+    pub fn is_same_asset(&self, tool_asset: &ToolAsset) -> bool {
+        let same_sha256 = match &tool_asset.sha256 {
+            None => true,
+            Some(sha256) => *sha256 == self.sha256,
+        };
+
+        let same_updated_at = match (&self.asset_updated_at, &tool_asset.asset.updated_at) {
+            (Some(installed), Some(updated_at)) => installed == updated_at,
+            _ => true,
+        };
+
+        let mut exe_names = tool_asset.installed_exe_names();
+        exe_names.sort();
+
+        self.tag == tool_asset.tag
+            && self.asset_id == tool_asset.asset.id
+            && self.exe_names().into_iter().eq(exe_names)
+            && same_sha256
+            && same_updated_at
+    }
+
+    /// Returns SHA-256 of the installed executables if it's the same asset
+    /// and nobody changed any of the executables since the installation
+    pub fn up_to_date_sha256(
+        &self,
+        tool_asset: &ToolAsset,
+        store_directory: &Path,
+    ) -> Option<BTreeMap<String, String>> {
+        if !self.is_same_asset(tool_asset) {
+            return None;
+        }
+
+        let is_unchanged = self.sha256.iter().all(|(exe_name, sha256)| {
+            matches!(sha256_digest(&store_directory.join(exe_name)), Ok(actual) if actual == *sha256)
+        });
+
+        if is_unchanged {
+            Some(self.sha256.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Names of all executables of the tool in the store directory
+    pub fn exe_names(&self) -> Vec<&str> {
+        self.sha256.keys().map(String::as_str).collect()
+    }
+
+    pub fn active_version(&self) -> InstalledVersion {
+        InstalledVersion {
+            tag: self.tag.clone(),
+            asset_id: self.asset_id,
+            asset_name: self.asset_name.clone(),
+            asset_size: self.asset_size,
+            asset_updated_at: self.asset_updated_at.clone(),
+            installed_at: self.installed_at,
+            sha256: self.sha256.clone(),
+        }
+    }
+
+    /// Make the most recent previous version active. Returns the version that
+    /// was active before or `None` if there's nothing to roll back to.
+    pub fn rollback(&mut self) -> Option<InstalledVersion> {
+        let version = self.previous.pop()?;
+        let discarded = self.active_version();
+
+        self.tag = version.tag;
+        self.asset_id = version.asset_id;
+        self.asset_name = version.asset_name;
+        self.asset_size = version.asset_size;
+        self.asset_updated_at = version.asset_updated_at;
+        self.installed_at = version.installed_at;
+        self.sha256 = version.sha256;
+
+        Some(discarded)
+    }
+}
+
+/// Check that the executable in the store directory is still the one
+/// installed by 'tool-sync'. Missing executables have nothing to protect.
+pub fn check_unchanged_exe(
+    store_directory: &Path,
+    exe_name: &str,
+    sha256: &str,
+) -> Result<(), String> {
+    let exe_path = store_directory.join(exe_name);
+
+    if !exe_path.is_file() {
+        return Ok(());
+    }
+
+    match sha256_digest(&exe_path) {
+        Err(e) => Err(e.to_string()),
+        Ok(actual) if actual != sha256 => Err(format!(
+            "the executable {} was changed after the installation",
+            exe_name
+        )),
+        Ok(_) => Ok(()),
+    }
+}
+
+fn now_unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use tempdir::TempDir;
+
+    use crate::infra::client::Client;
+    use crate::model::asset_name::mk_exe_name;
+    use crate::model::os::OS;
+    use crate::model::release::Asset;
+
+    fn rg_sha256(sha256: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([(mk_exe_name("rg", OS::Linux), String::from(sha256))])
+    }
+
+    fn installed_ripgrep() -> InstalledTool {
+        InstalledTool {
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            tag: String::from("13.0.0"),
+            asset_id: 42,
+            asset_name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            installed_at: 1666000000,
+            previous: vec![],
+            sha256: rg_sha256("abcdef"),
+        }
+    }
+
+    fn ripgrep_asset(tag: &str, asset_id: u32, sha256: Option<&str>) -> ToolAsset {
+        ToolAsset {
+            tool_name: String::from("ripgrep"),
+            tag: String::from(tag),
+            exe_names: vec![String::from("rg")],
+            install_as: None,
+            exe_path: None,
+            auto_exe: false,
+            os: OS::Linux,
+            asset: Asset {
+                id: asset_id,
+                name: String::from("ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz"),
+                size: 1000,
+                updated_at: None,
+            },
+            prerelease: false,
+            sha256: sha256.map(rg_sha256),
+            client: Client {
+                owner: String::from("BurntSushi"),
+                repo: String::from("ripgrep"),
+                version: String::from("latest"),
+                proxy: None,
+            },
+        }
+    }
+
+    #[test]
+    fn state_path_inside_store_directory() {
+        assert_eq!(
+            state_path(Path::new("bin")),
+            ["bin", ".tool-sync", "state.toml"]
+                .iter()
+                .collect::<PathBuf>()
+        );
+    }
+
+    #[test]
+    fn same_asset() {
+        let installed = installed_ripgrep();
+
+        assert!(installed.is_same_asset(&ripgrep_asset("13.0.0", 42, None)));
+        assert!(installed.is_same_asset(&ripgrep_asset("13.0.0", 42, Some("abcdef"))));
+    }
+
+    #[test]
+    fn different_asset() {
+        let installed = installed_ripgrep();
+
+        assert!(!installed.is_same_asset(&ripgrep_asset("14.0.0", 42, None)));
+        assert!(!installed.is_same_asset(&ripgrep_asset("13.0.0", 43, None)));
+        assert!(!installed.is_same_asset(&ripgrep_asset("13.0.0", 42, Some("fedcba"))));
+    }
+
+    #[test]
+    fn different_asset_update_time() {
+        let mut installed = installed_ripgrep();
+        installed.asset_updated_at = Some(String::from("2022-10-01T00:00:00Z"));
+
+        let mut tool_asset = ripgrep_asset("13.0.0", 42, None);
+        tool_asset.asset.updated_at = Some(String::from("2022-10-02T00:00:00Z"));
+
+        assert!(!installed.is_same_asset(&tool_asset));
+    }
+
+    #[test]
+    fn different_installed_name() {
+        let installed = installed_ripgrep();
+
+        let mut tool_asset = ripgrep_asset("13.0.0", 42, None);
+        tool_asset.install_as = Some(String::from("rg13"));
+
+        assert_eq!(tool_asset.installed_exe_names(), vec![String::from("rg13")]);
+        assert!(!installed.is_same_asset(&tool_asset));
+    }
+
+    #[test]
+    fn every_exe_checked_for_up_to_date() {
+        let store_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        fs::write(store_dir.path().join("fzf"), "fzf").unwrap();
+        fs::write(store_dir.path().join("fzf-tmux"), "fzf-tmux").unwrap();
+
+        let sha256 = |exe_name: &str| sha256_digest(&store_dir.path().join(exe_name)).unwrap();
+
+        let mut installed = installed_ripgrep();
+        installed.sha256 = BTreeMap::from([
+            (String::from("fzf"), sha256("fzf")),
+            (String::from("fzf-tmux"), sha256("fzf-tmux")),
+        ]);
+
+        let mut tool_asset = ripgrep_asset("13.0.0", 42, None);
+        tool_asset.exe_names = vec![String::from("fzf-tmux"), String::from("fzf")];
+
+        assert_eq!(
+            installed.up_to_date_sha256(&tool_asset, store_dir.path()),
+            Some(installed.sha256.clone())
+        );
+
+        fs::write(store_dir.path().join("fzf-tmux"), "changed").unwrap();
+        assert_eq!(
+            installed.up_to_date_sha256(&tool_asset, store_dir.path()),
+            None
+        );
+    }
+
+    #[test]
+    fn exe_owned_by_installed_tool() {
+        let state = StoreState {
+            tools: BTreeMap::from([(String::from("ripgrep"), installed_ripgrep())]),
+        };
+
+        assert_eq!(state.exe_owner("rg"), Some("ripgrep"));
+        assert_eq!(state.exe_owner("fd"), None);
+    }
+
+    #[test]
+    fn stale_exes_removed() {
+        let store_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        for exe_name in ["rg", "rg13", "rg12", "fd"] {
+            fs::write(store_dir.path().join(exe_name), exe_name).unwrap();
+        }
+
+        let sha256 = |exe_name: &str| sha256_digest(&store_dir.path().join(exe_name)).unwrap();
+        let previous_sha256 = BTreeMap::from([
+            (String::from("fd"), sha256("fd")),
+            (String::from("rg12"), String::from("changed")),
+            (String::from("rg13"), sha256("rg13")),
+        ]);
+
+        let mut ripgrep = installed_ripgrep();
+        ripgrep.sha256 = BTreeMap::from([(String::from("rg"), sha256("rg"))]);
+        let mut fd = installed_ripgrep();
+        fd.sha256 = BTreeMap::from([(String::from("fd"), sha256("fd"))]);
+
+        let state = StoreState {
+            tools: BTreeMap::from([(String::from("ripgrep"), ripgrep), (String::from("fd"), fd)]),
+        };
+        state.remove_stale_exes(store_dir.path(), &previous_sha256);
+
+        assert!(store_dir.path().join("rg").exists());
+        assert!(!store_dir.path().join("rg13").exists());
+        assert!(store_dir.path().join("rg12").exists());
+        assert!(store_dir.path().join("fd").exists());
+    }
+
+    #[test]
+    fn version_dir_inside_tool_dir() {
+        assert_eq!(
+            version_dir("ripgrep", "13.0.0"),
+            [".tool-sync", "ripgrep", "13.0.0"]
+                .iter()
+                .collect::<PathBuf>()
+        );
+        assert_eq!(
+            version_dir("tool", "release/1.0"),
+            [".tool-sync", "tool", "release_1.0"]
+                .iter()
+                .collect::<PathBuf>()
+        );
+    }
+
+    #[test]
+    fn record_keeps_previous_versions() {
+        let mut state = StoreState {
+            tools: BTreeMap::from([("ripgrep".to_owned(), installed_ripgrep())]),
+        };
+
+        let outdated = state.record(&ripgrep_asset("14.0.0", 43, None), rg_sha256("b"));
+        assert_eq!(outdated, vec![]);
+
+        let outdated = state.record(&ripgrep_asset("14.1.0", 44, None), rg_sha256("c"));
+        assert_eq!(outdated, vec![]);
+
+        let outdated = state.record(&ripgrep_asset("14.1.1", 45, None), rg_sha256("d"));
+        let outdated_tags: Vec<&str> = outdated.iter().map(|v| v.tag.as_str()).collect();
+        assert_eq!(outdated_tags, vec!["13.0.0"]);
+
+        let previous_tags: Vec<&str> = state.tools["ripgrep"]
+            .previous
+            .iter()
+            .map(|v| v.tag.as_str())
+            .collect();
+        assert_eq!(previous_tags, vec!["14.0.0", "14.1.0"]);
+    }
+
+    #[test]
+    fn record_same_tag_replaces_version() {
+        let mut state = StoreState {
+            tools: BTreeMap::from([("ripgrep".to_owned(), installed_ripgrep())]),
+        };
+
+        let outdated = state.record(&ripgrep_asset("13.0.0", 43, None), rg_sha256("b"));
+
+        assert_eq!(outdated, vec![]);
+        assert_eq!(state.tools["ripgrep"].previous, vec![]);
+        assert_eq!(state.tools["ripgrep"].asset_id, 43);
+    }
+
+    #[test]
+    fn rollback_to_previous_version() {
+        let mut state = StoreState {
+            tools: BTreeMap::from([("ripgrep".to_owned(), installed_ripgrep())]),
+        };
+        state.record(&ripgrep_asset("14.0.0", 43, None), rg_sha256("b"));
+
+        let installed = state.tools.get_mut("ripgrep").unwrap();
+        let discarded = installed.rollback().map(|v| v.tag);
+
+        assert_eq!(discarded, Some(String::from("14.0.0")));
+        assert_eq!(installed.tag, "13.0.0");
+        assert_eq!(installed.sha256, rg_sha256("abcdef"));
+        assert_eq!(installed.rollback(), None);
+    }
+
+    #[test]
+    fn state_roundtrip() {
+        let mut state = StoreState {
+            tools: BTreeMap::from([("ripgrep".to_owned(), installed_ripgrep())]),
+        };
+
+        let contents = toml::to_string(&state).expect("This should not fail");
+        let parsed: StoreState = toml::from_str(&contents).expect("This should not fail");
+        assert_eq!(parsed, state);
+
+        state.record(&ripgrep_asset("14.0.0", 43, None), rg_sha256("b"));
+
+        let contents = toml::to_string(&state).expect("This should not fail");
+
+        assert_eq!(toml::from_str(&contents), Ok(state));
+    }
+}
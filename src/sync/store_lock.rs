@@ -0,0 +1,118 @@
+//! This file contains the exclusive lock on the store directory. It prevents
+//! concurrent runs of 'tool-sync' (e.g. from a shell startup script and a
+//! manual run) from changing the same files at the same time.
+
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use crate::infra::err;
+
+use super::state::STATE_DIRECTORY;
+
+const LOCK_FILE: &str = "store.lock";
+
+/// Advisory lock on the store directory. The lock is released when the value
+/// is dropped or when the process exits.
+pub struct StoreLock {
+    file: File,
+}
+
+/// Whether to wait for another 'tool-sync' process to release the lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockWait {
+    Wait,
+    NoWait,
+}
+
+pub fn store_lock_path(store_directory: &Path) -> PathBuf {
+    store_directory.join(STATE_DIRECTORY).join(LOCK_FILE)
+}
+
+impl StoreLock {
+    /// Take the exclusive lock on the store directory. Aborts if the lock is
+    /// taken by another process and `wait` is `LockWait::NoWait`.
+    pub fn acquire(store_directory: &Path, wait: LockWait) -> StoreLock {
+        let lock_path = store_lock_path(store_directory);
+
+        let file = match open_lock_file(&lock_path) {
+            Ok(file) => file,
+            Err(e) => err::abort_with(format!(
+                "Error opening the store lock at path {}: {}",
+                lock_path.display(),
+                e
+            )),
+        };
+
+        if file.try_lock_exclusive().is_ok() {
+            return StoreLock { file };
+        }
+
+        if wait == LockWait::NoWait {
+            err::abort_with(format!(
+                "Another 'tool-sync' process is using the store directory: {}",
+                store_directory.display()
+            ));
+        }
+
+        eprintln!(
+            "Waiting for another 'tool-sync' process to finish with the store directory: {}",
+            store_directory.display()
+        );
+
+        if let Err(e) = file.lock_exclusive() {
+            err::abort_with(format!(
+                "Error locking the store directory {}: {}",
+                store_directory.display(),
+                e
+            ));
+        }
+
+        StoreLock { file }
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+fn open_lock_file(lock_path: &Path) -> std::io::Result<File> {
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempdir::TempDir;
+
+    #[test]
+    fn lock_released_on_drop() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+
+        let lock = StoreLock::acquire(tmp_dir.path(), LockWait::NoWait);
+        drop(lock);
+
+        let _lock = StoreLock::acquire(tmp_dir.path(), LockWait::NoWait);
+    }
+
+    #[test]
+    fn lock_is_exclusive() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+
+        let _lock = StoreLock::acquire(tmp_dir.path(), LockWait::NoWait);
+        let other = open_lock_file(&store_lock_path(tmp_dir.path())).unwrap();
+
+        assert!(other.try_lock_exclusive().is_err());
+    }
+}
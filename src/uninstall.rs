@@ -0,0 +1,254 @@
+use console::{style, Emoji};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::schema::Config;
+use crate::config::toml;
+use crate::infra::err;
+use crate::sync::lock::{self, Lockfile};
+use crate::sync::state::{check_unchanged_exe, tool_dir, InstalledTool, StoreState};
+use crate::sync::store_lock::{LockWait, StoreLock};
+
+const REMOVED: Emoji<'_, '_> = Emoji("🗑️  ", "* ");
+const KEPT: Emoji<'_, '_> = Emoji("⚠️  ", "! ");
+
+/// Remove a single tool installed by 'tool-sync'
+pub fn uninstall(config_path: PathBuf, name: String, proxy: Option<String>) {
+    let lock_path = lock::lock_path(&config_path);
+    toml::with_parsed_file(config_path, proxy, |config| {
+        uninstall_tool(config, name, lock_path)
+    })
+}
+
+/// Remove all tools installed by 'tool-sync' but no longer listed in the config
+pub fn prune(config_path: PathBuf, proxy: Option<String>) {
+    let lock_path = lock::lock_path(&config_path);
+    toml::with_parsed_file(config_path, proxy, |config| prune_tools(config, lock_path))
+}
+
+fn uninstall_tool(config: Config, name: String, lock_path: PathBuf) {
+    let store_directory = config.ensure_store_directory();
+    let _store_lock = StoreLock::acquire(&store_directory, LockWait::Wait);
+    let mut state = StoreState::read(&store_directory);
+
+    if !state.tools.contains_key(&name) {
+        err::abort_with(format!(
+            "The '{}' tool is not installed by 'tool-sync' in: {}",
+            name,
+            store_directory.display()
+        ));
+    }
+
+    remove_tools(&store_directory, &mut state, &lock_path, &[name]);
+}
+
+fn prune_tools(config: Config, lock_path: PathBuf) {
+    let store_directory = config.ensure_store_directory();
+    let _store_lock = StoreLock::acquire(&store_directory, LockWait::Wait);
+    let mut state = StoreState::read(&store_directory);
+
+    let unlisted_tools: Vec<String> = state
+        .tools
+        .keys()
+        .filter(|name| !config.tools.contains_key(*name))
+        .cloned()
+        .collect();
+
+    if unlisted_tools.is_empty() {
+        eprintln!("Nothing to prune: all installed tools are listed in the config");
+        return;
+    }
+
+    remove_tools(&store_directory, &mut state, &lock_path, &unlisted_tools);
+}
+
+/// Remove executables of the given tools and forget about these tools in the
+/// store state and in the lock file. Tools with kept executables stay
+/// installed.
+fn remove_tools(
+    store_directory: &Path,
+    state: &mut StoreState,
+    lock_path: &Path,
+    names: &[String],
+) {
+    let mut lockfile = Lockfile::read(lock_path);
+    let mut is_lockfile_changed = false;
+
+    for name in names {
+        if let Some(installed) = state.tools.remove(name) {
+            let tool = style(name).cyan().bold();
+
+            let exe_names = installed.exe_names().join(", ");
+
+            match remove_exe(store_directory, state, &installed) {
+                Ok(()) => {
+                    eprintln!("{}{} Uninstalled {}", REMOVED, tool, exe_names);
+
+                    let _ = fs::remove_dir_all(store_directory.join(tool_dir(name)));
+
+                    // The lock file may be shared with other platforms
+                    if let Some(platform) = lockfile.find_platform(name, installed.asset_id) {
+                        is_lockfile_changed |= lockfile.unpin(name, &platform);
+                    }
+                }
+                Err(reason) => {
+                    eprintln!("{}{} Kept {}: {}", KEPT, tool, exe_names, reason);
+
+                    // The kept executables still point to the installed
+                    // versions
+                    state.tools.insert(name.clone(), installed);
+                }
+            }
+        }
+    }
+
+    state.write(store_directory);
+
+    if is_lockfile_changed {
+        lockfile.write(lock_path);
+    }
+}
+
+/// Remove the executables only if all of them are exactly the ones installed
+/// by 'tool-sync' and no other tool uses any of them
+fn remove_exe(
+    store_directory: &Path,
+    state: &StoreState,
+    installed: &InstalledTool,
+) -> Result<(), String> {
+    let exe_names = installed.exe_names();
+
+    if let Some((other_tool, _)) = state.tools.iter().find(|(_, other)| {
+        other
+            .exe_names()
+            .iter()
+            .any(|exe_name| exe_names.contains(exe_name))
+    }) {
+        return Err(format!("the executable is used by '{}'", other_tool));
+    }
+
+    // Nothing is removed if any of the executables was changed
+    for (exe_name, sha256) in &installed.sha256 {
+        check_unchanged_exe(store_directory, exe_name, sha256)?;
+    }
+
+    for exe_name in exe_names {
+        let exe_path = store_directory.join(exe_name);
+
+        if exe_path.symlink_metadata().is_ok() {
+            fs::remove_file(&exe_path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use tempdir::TempDir;
+
+    use crate::infra::file::sha256_digest;
+    use crate::sync::lock::LockedTool;
+    use crate::sync::state::version_dir;
+
+    fn installed_fzf(sha256: String) -> InstalledTool {
+        InstalledTool {
+            owner: String::from("junegunn"),
+            repo: String::from("fzf"),
+            tag: String::from("0.35.0"),
+            asset_id: 42,
+            asset_name: String::from("fzf-0.35.0-linux_amd64.tar.gz"),
+            asset_size: 1000,
+            asset_updated_at: None,
+            installed_at: 1666000000,
+            previous: vec![],
+            sha256: BTreeMap::from([(String::from("fzf"), sha256)]),
+        }
+    }
+
+    fn locked_fzf(sha256: String) -> Lockfile {
+        Lockfile {
+            tools: BTreeMap::from([(
+                String::from("fzf"),
+                BTreeMap::from([(
+                    String::from("linux-x86_64"),
+                    LockedTool {
+                        tag: String::from("0.35.0"),
+                        asset_id: 42,
+                        asset_name: String::from("fzf-0.35.0-linux_amd64.tar.gz"),
+                        asset_size: 1000,
+                        asset_updated_at: None,
+                        prerelease: false,
+                        sha256: BTreeMap::from([(String::from("fzf"), sha256)]),
+                    },
+                )]),
+            )]),
+        }
+    }
+
+    /// Installs fzf into a temporary store directory and returns the store
+    /// directory and the lock file path
+    fn install_fzf(tmp_dir: &TempDir) -> (PathBuf, PathBuf) {
+        let store_directory = tmp_dir.path().join("bin");
+        let version_dir = store_directory.join(version_dir("fzf", "0.35.0"));
+        fs::create_dir_all(&version_dir).unwrap();
+        fs::write(version_dir.join("fzf"), "fzf").unwrap();
+        fs::write(store_directory.join("fzf"), "fzf").unwrap();
+
+        let sha256 = sha256_digest(&store_directory.join("fzf")).unwrap();
+
+        let state = StoreState {
+            tools: BTreeMap::from([(String::from("fzf"), installed_fzf(sha256.clone()))]),
+        };
+        state.write(&store_directory);
+
+        let lock_path = tmp_dir.path().join(".tool.lock");
+        locked_fzf(sha256).write(&lock_path);
+
+        (store_directory, lock_path)
+    }
+
+    #[test]
+    fn uninstall_removes_tool() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        let (store_directory, lock_path) = install_fzf(&tmp_dir);
+        let mut state = StoreState::read(&store_directory);
+
+        remove_tools(
+            &store_directory,
+            &mut state,
+            &lock_path,
+            &[String::from("fzf")],
+        );
+
+        assert!(!store_directory.join("fzf").exists());
+        assert!(!store_directory.join(tool_dir("fzf")).exists());
+        assert_eq!(StoreState::read(&store_directory), StoreState::default());
+        assert_eq!(Lockfile::read(&lock_path), Lockfile::default());
+    }
+
+    #[test]
+    fn changed_exe_keeps_tool() {
+        let tmp_dir = TempDir::new("tool-sync-test").expect("This should not fail");
+        let (store_directory, lock_path) = install_fzf(&tmp_dir);
+        let mut state = StoreState::read(&store_directory);
+        let lockfile = Lockfile::read(&lock_path);
+
+        fs::write(store_directory.join("fzf"), "changed").unwrap();
+
+        remove_tools(
+            &store_directory,
+            &mut state,
+            &lock_path,
+            &[String::from("fzf")],
+        );
+
+        assert!(store_directory.join("fzf").exists());
+        assert!(store_directory.join(version_dir("fzf", "0.35.0")).exists());
+        assert!(StoreState::read(&store_directory).tools.contains_key("fzf"));
+        assert_eq!(Lockfile::read(&lock_path), lockfile);
+    }
+}